@@ -0,0 +1,99 @@
+#[allow(dead_code)]
+#[path = "../src/rope.rs"]
+mod rope;
+
+use rope::Rope;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const LINES: usize = 500_000;
+const EDITS: usize = 2_000;
+
+trait Lines: FromIterator<String> {
+    fn insert_line(&mut self, index: usize, line: String);
+    fn remove_line(&mut self, index: usize) -> String;
+    fn line(&self, index: usize) -> &String;
+}
+
+impl Lines for Vec<String> {
+    fn insert_line(&mut self, index: usize, line: String) {
+        self.insert(index, line);
+    }
+
+    fn remove_line(&mut self, index: usize) -> String {
+        self.remove(index)
+    }
+
+    fn line(&self, index: usize) -> &String {
+        &self[index]
+    }
+}
+
+impl Lines for Rope<String> {
+    fn insert_line(&mut self, index: usize, line: String) {
+        self.insert(index, line);
+    }
+
+    fn remove_line(&mut self, index: usize) -> String {
+        self.remove(index)
+    }
+
+    fn line(&self, index: usize) -> &String {
+        &self[index]
+    }
+}
+
+fn log() -> impl Iterator<Item = String> {
+    (0..LINES).map(|i| format!("[{i:>8}] INFO request handled in {}ms", i % 997))
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn bench<L: Lines>(name: &str) {
+    let mut lines: L = log().collect();
+
+    let insert = time(|| {
+        for i in 0..EDITS {
+            lines.insert_line(10 + i % 7, String::from("inserted near the top"));
+        }
+    });
+
+    let remove = time(|| {
+        for _ in 0..EDITS {
+            black_box(lines.remove_line(10));
+        }
+    });
+
+    let split = time(|| {
+        for i in 0..EDITS {
+            let line = lines.remove_line(i);
+            let (head, tail) = line.split_at(line.len() / 2);
+            lines.insert_line(i, tail.to_string());
+            lines.insert_line(i, head.to_string());
+        }
+    });
+
+    let scan = time(|| {
+        for i in (0..LINES).step_by(LINES / 1_000) {
+            black_box(lines.line(i));
+        }
+    });
+
+    println!(
+        "{name:<12} insert {:>10.2?}  remove {:>10.2?}  split {:>10.2?}  access {:>10.2?}",
+        insert / EDITS as u32,
+        remove / EDITS as u32,
+        split / EDITS as u32,
+        scan / 1_000,
+    );
+}
+
+fn main() {
+    println!("{LINES} lines, {EDITS} edits near the top (times per operation)");
+    bench::<Vec<String>>("Vec<String>");
+    bench::<Rope<String>>("Rope<String>");
+}
@@ -0,0 +1,19 @@
+use crate::Document;
+use crate::Position;
+
+#[derive(Default)]
+pub struct Buffer {
+    pub document: Document,
+    pub cursor_position: Position,
+    pub offset: Position,
+}
+
+impl From<Document> for Buffer {
+    fn from(document: Document) -> Buffer {
+        Buffer {
+            document,
+            cursor_position: Position::default(),
+            offset: Position::default(),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Command {
+    Quit,
+    Save,
+    Find,
+    RegexFind,
+    Replace,
+    Open,
+    PickBuffer,
+    NextBuffer,
+    PreviousBuffer,
+    ConvertLineEndings,
+    Undo,
+    Redo,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    SelectUp,
+    SelectDown,
+    SelectLeft,
+    SelectRight,
+    Cut,
+    Copy,
+    Paste,
+    InsertNewline,
+    InsertTab,
+    Delete,
+    Backspace,
+    SplitHorizontal,
+    SplitVertical,
+    ClosePane,
+    NextPane,
+    FocusUp,
+    FocusDown,
+    FocusLeft,
+    FocusRight,
+    GrowHeight,
+    ShrinkHeight,
+    GrowWidth,
+    ShrinkWidth,
+    CommandPalette,
+    GotoLine,
+    ToggleBackupOnSave,
+    CycleLineNumbers,
+    ToggleSoftWrap,
+    IndentLines,
+    DedentLines,
+    MatchBracket,
+}
+
+const COMMANDS: &[(Command, &str, &str)] = &[
+    (Command::Quit, "quit", "Quit the editor"),
+    (Command::Save, "save", "Save the current buffer"),
+    (Command::Find, "find", "Search for text"),
+    (
+        Command::RegexFind,
+        "regex_find",
+        "Search for a regular expression",
+    ),
+    (Command::Replace, "replace", "Replace a regular expression"),
+    (Command::Open, "open", "Open a file in a new buffer"),
+    (
+        Command::PickBuffer,
+        "pick_buffer",
+        "Switch to another buffer",
+    ),
+    (
+        Command::NextBuffer,
+        "next_buffer",
+        "Switch to the next buffer",
+    ),
+    (
+        Command::PreviousBuffer,
+        "previous_buffer",
+        "Switch to the previous buffer",
+    ),
+    (
+        Command::ConvertLineEndings,
+        "convert_line_endings",
+        "Convert the line endings to LF or CRLF",
+    ),
+    (Command::Undo, "undo", "Undo the last edit"),
+    (Command::Redo, "redo", "Redo the last undone edit"),
+    (Command::MoveUp, "move_up", "Move the cursor up"),
+    (Command::MoveDown, "move_down", "Move the cursor down"),
+    (Command::MoveLeft, "move_left", "Move the cursor left"),
+    (Command::MoveRight, "move_right", "Move the cursor right"),
+    (Command::SelectUp, "select_up", "Extend the selection up"),
+    (
+        Command::SelectDown,
+        "select_down",
+        "Extend the selection down",
+    ),
+    (
+        Command::SelectLeft,
+        "select_left",
+        "Extend the selection left",
+    ),
+    (
+        Command::SelectRight,
+        "select_right",
+        "Extend the selection right",
+    ),
+    (Command::Cut, "cut", "Cut the selection"),
+    (Command::Copy, "copy", "Copy the selection"),
+    (Command::Paste, "paste", "Paste the last cut or copied text"),
+    (
+        Command::InsertNewline,
+        "insert_newline",
+        "Insert a line break",
+    ),
+    (Command::InsertTab, "insert_tab", "Insert an indentation"),
+    (
+        Command::IndentLines,
+        "indent_lines",
+        "Indent the selected lines",
+    ),
+    (
+        Command::DedentLines,
+        "dedent_lines",
+        "Dedent the selected lines",
+    ),
+    (
+        Command::Delete,
+        "delete",
+        "Delete the character under the cursor",
+    ),
+    (
+        Command::Backspace,
+        "backspace",
+        "Delete the character before the cursor",
+    ),
+    (
+        Command::SplitHorizontal,
+        "split_horizontal",
+        "Split the pane horizontally",
+    ),
+    (
+        Command::SplitVertical,
+        "split_vertical",
+        "Split the pane vertically",
+    ),
+    (Command::ClosePane, "close_pane", "Close the pane"),
+    (Command::NextPane, "next_pane", "Focus the next pane"),
+    (Command::FocusUp, "focus_up", "Focus the pane above"),
+    (Command::FocusDown, "focus_down", "Focus the pane below"),
+    (
+        Command::FocusLeft,
+        "focus_left",
+        "Focus the pane on the left",
+    ),
+    (
+        Command::FocusRight,
+        "focus_right",
+        "Focus the pane on the right",
+    ),
+    (Command::GrowHeight, "grow_height", "Make the pane taller"),
+    (
+        Command::ShrinkHeight,
+        "shrink_height",
+        "Make the pane shorter",
+    ),
+    (Command::GrowWidth, "grow_width", "Make the pane wider"),
+    (
+        Command::ShrinkWidth,
+        "shrink_width",
+        "Make the pane narrower",
+    ),
+    (
+        Command::CommandPalette,
+        "command_palette",
+        "Run a command by name",
+    ),
+    (Command::GotoLine, "goto_line", "Jump to a line number"),
+    (
+        Command::MatchBracket,
+        "match_bracket",
+        "Jump to the matching bracket",
+    ),
+    (
+        Command::ToggleBackupOnSave,
+        "toggle_backup_on_save",
+        "Toggle writing a backup file on save",
+    ),
+    (
+        Command::CycleLineNumbers,
+        "cycle_line_numbers",
+        "Switch between off, absolute, relative and hybrid line numbers",
+    ),
+    (
+        Command::ToggleSoftWrap,
+        "toggle_soft_wrap",
+        "Toggle wrapping long lines",
+    ),
+];
+
+impl Command {
+    pub fn all() -> impl Iterator<Item = Command> {
+        COMMANDS.iter().map(|(command, _, _)| *command)
+    }
+
+    pub fn from_name(name: &str) -> Option<Command> {
+        COMMANDS
+            .iter()
+            .find(|(_, command_name, _)| *command_name == name)
+            .map(|(command, _, _)| *command)
+    }
+
+    pub fn name(self) -> &'static str {
+        COMMANDS
+            .iter()
+            .find(|(command, _, _)| *command == self)
+            .map(|(_, name, _)| *name)
+            .unwrap_or_default()
+    }
+
+    pub fn description(self) -> &'static str {
+        COMMANDS
+            .iter()
+            .find(|(command, _, _)| *command == self)
+            .map(|(_, _, description)| *description)
+            .unwrap_or_default()
+    }
+}
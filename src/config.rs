@@ -0,0 +1,279 @@
+use crate::keymap;
+use crate::Command;
+use crate::ConfigError;
+use crate::Keymap;
+use crate::LineNumbers;
+use crossterm::style::Color;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use toml::value::Table;
+use toml::Value;
+
+const PROJECT_FILE: &str = ".trusty.toml";
+const COLOR: &str = "a color name, \"#rrggbb\" or an ANSI value (0-255)";
+
+#[derive(Clone)]
+pub struct Config {
+    pub fg_color: Color,
+    pub bg_color: Color,
+    pub status_bar_fg_color: Color,
+    pub status_bar_bg_color: Color,
+    pub inactive_status_bar_bg_color: Color,
+    pub tildes_color: Color,
+    pub match_bg_color: Color,
+    pub selection_bg_color: Color,
+    pub bracket_bg_color: Color,
+    pub line_number_color: Color,
+    pub current_line_number_color: Color,
+    pub tab_size: usize,
+    pub status_message_duration: u64,
+    pub backup_on_save: bool,
+    pub vi_mode: bool,
+    pub osc52_clipboard: bool,
+    pub line_numbers: LineNumbers,
+    pub soft_wrap: bool,
+    pub auto_indent: bool,
+    pub indent_openers: HashMap<String, String>,
+    pub keymap: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            fg_color: Color::White,
+            bg_color: Color::Black,
+            status_bar_fg_color: Color::Black,
+            status_bar_bg_color: Color::White,
+            inactive_status_bar_bg_color: Color::DarkGrey,
+            tildes_color: Color::DarkGrey,
+            match_bg_color: Color::Yellow,
+            selection_bg_color: Color::Blue,
+            bracket_bg_color: Color::DarkCyan,
+            line_number_color: Color::DarkGrey,
+            current_line_number_color: Color::Yellow,
+            tab_size: 4,
+            status_message_duration: 5,
+            backup_on_save: false,
+            vi_mode: false,
+            osc52_clipboard: false,
+            line_numbers: LineNumbers::Off,
+            soft_wrap: false,
+            auto_indent: true,
+            indent_openers: HashMap::new(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(&mut self) -> Result<(), ConfigError> {
+        for path in paths() {
+            self.merge_file(&path)?;
+        }
+
+        Ok(())
+    }
+
+    fn merge_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let name = path.display().to_string();
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(ConfigError::Io(name, error)),
+        };
+
+        let table = toml::from_str::<Table>(&contents)
+            .map_err(|error| ConfigError::Parse(name.clone(), error))?;
+
+        let mut config = self.clone();
+        config.merge(&name, &table)?;
+        *self = config;
+
+        Ok(())
+    }
+
+    fn merge(&mut self, path: &str, table: &Table) -> Result<(), ConfigError> {
+        for (key, value) in table {
+            match key.as_str() {
+                "tab_size" => self.tab_size = integer(path, key, value, 1..=16)? as usize,
+                "status_message_duration" => {
+                    self.status_message_duration = integer(path, key, value, 0..=3600)? as u64
+                }
+                "backup_on_save" => self.backup_on_save = boolean(path, key, value)?,
+                "vi_mode" => self.vi_mode = boolean(path, key, value)?,
+                "auto_indent" => self.auto_indent = boolean(path, key, value)?,
+                "indent_openers" => {
+                    self.merge_indent_openers(path, table_value(path, key, value)?)?
+                }
+                "soft_wrap" => self.soft_wrap = boolean(path, key, value)?,
+                "osc52_clipboard" => self.osc52_clipboard = boolean(path, key, value)?,
+                "line_numbers" => {
+                    self.line_numbers =
+                        value
+                            .as_str()
+                            .and_then(LineNumbers::from_name)
+                            .ok_or_else(|| {
+                                ConfigError::InvalidValue(
+                                    path.to_string(),
+                                    key.to_string(),
+                                    "\"off\", \"absolute\", \"relative\" or \"hybrid\"".to_string(),
+                                )
+                            })?
+                }
+                "colors" => self.merge_colors(path, table_value(path, key, value)?)?,
+                "keys" => self.merge_keys(path, table_value(path, key, value)?)?,
+                _ => return Err(ConfigError::UnknownKey(path.to_string(), key.to_string())),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn merge_colors(&mut self, path: &str, table: &Table) -> Result<(), ConfigError> {
+        for (key, value) in table {
+            let key = format!("colors.{key}");
+            let color = match key.as_str() {
+                "colors.foreground" => &mut self.fg_color,
+                "colors.background" => &mut self.bg_color,
+                "colors.status_bar_foreground" => &mut self.status_bar_fg_color,
+                "colors.status_bar_background" => &mut self.status_bar_bg_color,
+                "colors.inactive_status_bar_background" => &mut self.inactive_status_bar_bg_color,
+                "colors.tildes" => &mut self.tildes_color,
+                "colors.match_background" => &mut self.match_bg_color,
+                "colors.selection_background" => &mut self.selection_bg_color,
+                "colors.bracket_background" => &mut self.bracket_bg_color,
+                "colors.line_number" => &mut self.line_number_color,
+                "colors.current_line_number" => &mut self.current_line_number_color,
+                _ => return Err(ConfigError::UnknownKey(path.to_string(), key)),
+            };
+
+            *color = parse_color(value).ok_or_else(|| {
+                ConfigError::InvalidValue(path.to_string(), key, COLOR.to_string())
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn merge_indent_openers(&mut self, path: &str, table: &Table) -> Result<(), ConfigError> {
+        for (file_type, value) in table {
+            let openers = value.as_str().ok_or_else(|| {
+                ConfigError::InvalidValue(
+                    path.to_string(),
+                    format!("indent_openers.{file_type}"),
+                    "a string of characters".to_string(),
+                )
+            })?;
+
+            self.indent_openers
+                .insert(file_type.to_lowercase(), openers.to_string());
+        }
+
+        Ok(())
+    }
+
+    fn merge_keys(&mut self, path: &str, table: &Table) -> Result<(), ConfigError> {
+        for (keys, value) in table {
+            let sequence = keymap::parse_keys(keys)
+                .ok_or_else(|| ConfigError::InvalidKeys(path.to_string(), keys.to_string()))?;
+
+            let name = value.as_str().ok_or_else(|| {
+                ConfigError::InvalidValue(
+                    path.to_string(),
+                    format!("keys.{keys}"),
+                    "a command name or \"none\"".to_string(),
+                )
+            })?;
+
+            if name == "none" {
+                self.keymap.unbind(&sequence);
+            } else {
+                let command = Command::from_name(name).ok_or_else(|| {
+                    ConfigError::UnknownCommand(path.to_string(), name.to_string())
+                })?;
+                self.keymap.bind(sequence, command);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    if let Some(dir) = config_home {
+        paths.push(dir.join("trusty").join("config.toml"));
+    }
+
+    if let Ok(dir) = env::current_dir() {
+        if let Some(path) = dir
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_FILE))
+            .find(|path| path.is_file())
+        {
+            paths.push(path);
+        }
+    }
+
+    paths
+}
+
+fn integer(
+    path: &str,
+    key: &str,
+    value: &Value,
+    range: RangeInclusive<i64>,
+) -> Result<i64, ConfigError> {
+    match value {
+        Value::Integer(n) if range.contains(n) => Ok(*n),
+        _ => Err(ConfigError::InvalidValue(
+            path.to_string(),
+            key.to_string(),
+            format!("an integer between {} and {}", range.start(), range.end()),
+        )),
+    }
+}
+
+fn boolean(path: &str, key: &str, value: &Value) -> Result<bool, ConfigError> {
+    value.as_bool().ok_or_else(|| {
+        ConfigError::InvalidValue(
+            path.to_string(),
+            key.to_string(),
+            "true or false".to_string(),
+        )
+    })
+}
+
+fn table_value<'a>(path: &str, key: &str, value: &'a Value) -> Result<&'a Table, ConfigError> {
+    value.as_table().ok_or_else(|| {
+        ConfigError::InvalidValue(path.to_string(), key.to_string(), "a table".to_string())
+    })
+}
+
+fn parse_color(value: &Value) -> Option<Color> {
+    match value {
+        Value::Integer(n) => u8::try_from(*n).ok().map(Color::AnsiValue),
+        Value::String(name) => match name.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 && hex.chars().all(|ch| ch.is_ascii_hexdigit()) => {
+                let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+                Some(Color::Rgb {
+                    r: channel(0)?,
+                    g: channel(2)?,
+                    b: channel(4)?,
+                })
+            }
+            Some(_) => None,
+            None => Color::try_from(name.as_str()).ok(),
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,29 @@
+use std::{fmt, io};
+
+pub enum ConfigError {
+    Io(String, io::Error),
+    Parse(String, toml::de::Error),
+    UnknownKey(String, String),
+    InvalidValue(String, String, String),
+    InvalidKeys(String, String),
+    UnknownCommand(String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, error) => write!(f, "{path}: {error}"),
+            ConfigError::Parse(path, error) => write!(f, "{path}: {error}"),
+            ConfigError::UnknownKey(path, key) => write!(f, "{path}: unknown key '{key}'"),
+            ConfigError::InvalidValue(path, key, expected) => {
+                write!(f, "{path}: invalid value for '{key}', expected {expected}")
+            }
+            ConfigError::InvalidKeys(path, keys) => {
+                write!(f, "{path}: invalid key sequence '{keys}'")
+            }
+            ConfigError::UnknownCommand(path, name) => {
+                write!(f, "{path}: unknown command '{name}'")
+            }
+        }
+    }
+}
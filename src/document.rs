@@ -1,28 +1,190 @@
+use crate::file_type::FileType;
+use crate::highlighting::State;
+use crate::history::{Edit, History};
+use crate::rope::Rope;
+use crate::DocumentError;
+use crate::Indent;
+use crate::LineEnding;
 use crate::Position;
 use crate::Row;
-use std::io::Write;
-use std::{cmp, fs};
+use crate::SearchDirection;
+use regex::Regex;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::{cmp, fs, mem, process};
+use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Default, Clone)]
+const BINARY_PROBE_LEN: usize = 8192;
+const BRACKET_SCAN_ROWS: usize = 10_000;
+const DEFAULT_TAB_SIZE: usize = 4;
+
+#[derive(Clone)]
 pub struct Document {
     pub filename: Option<String>,
-    rows: Vec<Row>,
+    rows: Rope<Row>,
     dirty: bool,
+    history: History,
+    file_type: FileType,
+    line_ending: LineEnding,
+    mixed_line_endings: bool,
+    missing_final_newline: bool,
+    bom: bool,
+    read_only: bool,
+    indent: Option<Indent>,
+    tab_size: usize,
+    changes: Vec<Edit>,
+}
+
+impl Default for Document {
+    fn default() -> Document {
+        Document {
+            filename: None,
+            rows: Rope::default(),
+            dirty: false,
+            history: History::default(),
+            file_type: FileType::default(),
+            line_ending: LineEnding::default(),
+            mixed_line_endings: false,
+            missing_final_newline: false,
+            bom: false,
+            read_only: false,
+            indent: None,
+            tab_size: DEFAULT_TAB_SIZE,
+            changes: Vec::new(),
+        }
+    }
 }
 
 impl Document {
-    pub fn open(filename: &str) -> Document {
-        let rows: Vec<Row> = if let Ok(contents) = fs::read_to_string(filename) {
-            contents.lines().map(Row::from).collect()
+    pub fn open(filename: &str) -> Result<Document, DocumentError> {
+        if Path::new(filename).is_dir() {
+            return Err(DocumentError::IsDirectory(filename.to_string()));
+        }
+
+        let mut document = Document {
+            filename: Some(filename.to_string()),
+            file_type: FileType::from(filename),
+            ..Document::default()
+        };
+
+        match fs::read(filename) {
+            Ok(bytes) => {
+                let binary = bytes.iter().take(BINARY_PROBE_LEN).any(|byte| *byte == 0);
+
+                match String::from_utf8(bytes) {
+                    Ok(contents) if !binary => document.load(&contents),
+                    Ok(contents) => {
+                        document.load(&sanitize(&contents));
+                        document.read_only = true;
+                    }
+                    Err(error) => {
+                        document.load(&sanitize(&String::from_utf8_lossy(error.as_bytes())));
+                        document.read_only = true;
+                    }
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(DocumentError::from_io(filename, error)),
+        }
+
+        document.highlight(0, document.len());
+
+        Ok(document)
+    }
+
+    pub fn indent(&self) -> Option<Indent> {
+        self.indent
+    }
+
+    pub fn tab_size(&self) -> usize {
+        self.tab_size
+    }
+
+    pub fn set_tab_size(&mut self, tab_size: usize) {
+        self.tab_size = tab_size.max(1);
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn load(&mut self, contents: &str) {
+        let contents = if let Some(contents) = contents.strip_prefix('\u{feff}') {
+            self.bom = true;
+            contents
         } else {
-            Vec::new()
+            contents
         };
 
-        Document {
-            rows,
-            filename: Some(filename.to_string()),
-            dirty: false,
+        let mut lines: Vec<&str> = contents.split('\n').collect();
+        let last = lines.pop().unwrap_or_default();
+        let (mut lf, mut crlf) = (0, 0);
+
+        let mut rows: Vec<Row> = lines
+            .into_iter()
+            .map(|line| {
+                let (line, ending) = if let Some(line) = line.strip_suffix('\r') {
+                    crlf += 1;
+                    (line, LineEnding::Crlf)
+                } else {
+                    lf += 1;
+                    (line, LineEnding::Lf)
+                };
+
+                let mut row = Row::from(line);
+                row.set_ending(ending);
+                row
+            })
+            .collect();
+
+        self.line_ending = if crlf > lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        };
+        self.mixed_line_endings = lf > 0 && crlf > 0;
+
+        if !last.is_empty() {
+            let mut row = Row::from(last);
+            row.set_ending(self.line_ending);
+            rows.push(row);
+            self.missing_final_newline = true;
         }
+
+        self.rows = rows.into_iter().collect();
+        self.indent = Indent::detect(contents.lines());
+    }
+
+    pub fn line_ending(&self) -> Option<LineEnding> {
+        if self.mixed_line_endings {
+            None
+        } else {
+            Some(self.line_ending)
+        }
+    }
+
+    pub fn set_line_ending(&mut self, ending: LineEnding) -> Result<(), DocumentError> {
+        self.check_writable()?;
+
+        for y in 0..self.len() {
+            self.rows[y].set_ending(ending);
+        }
+
+        self.line_ending = ending;
+        self.mixed_line_endings = false;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    pub fn set_filename(&mut self, filename: String) {
+        self.file_type = FileType::from(filename.as_str());
+        self.filename = Some(filename);
+        self.highlight(0, self.len());
+    }
+
+    pub fn file_type(&self) -> &FileType {
+        &self.file_type
     }
 
     pub fn is_dirty(&self) -> bool {
@@ -33,66 +195,319 @@ impl Document {
         self.rows.len()
     }
 
-    pub fn row(&self, i: u16) -> Option<&Row> {
-        self.rows.get(i as usize)
+    pub fn row(&self, i: usize) -> Option<&Row> {
+        self.rows.get(i)
     }
 
-    pub fn insert(&mut self, ch: char, position: &Position) {
-        self.dirty = true;
-
-        if let Some(row) = self.rows.get_mut(position.y as usize) {
-            let at = cmp::min(position.x as usize, row.len());
-            row.insert(ch, at);
+    pub fn insert(&mut self, ch: char, position: &Position) -> Result<(), DocumentError> {
+        let edit = if let Some(row) = self.rows.get(position.y) {
+            Edit::Insert {
+                at: Position::new(cmp::min(position.x, row.len()), position.y),
+                text: ch.to_string(),
+            }
+        } else if let Some(last) = self.rows.last() {
+            Edit::Insert {
+                at: Position::new(last.len(), self.len() - 1),
+                text: format!("\n{ch}"),
+            }
         } else {
-            let mut row = Row::default();
-            row.insert(ch, 0);
-            self.rows.push(row);
+            Edit::Insert {
+                at: Position::default(),
+                text: ch.to_string(),
+            }
+        };
+
+        self.record(edit)
+    }
+
+    pub fn insert_newline(&mut self, at: &Position) -> Result<(), DocumentError> {
+        if at.y > self.len() {
+            return Ok(());
         }
+
+        let at = if let Some(row) = self.rows.get(at.y) {
+            Position::new(cmp::min(at.x, row.len()), at.y)
+        } else if let Some(last) = self.rows.last() {
+            Position::new(last.len(), self.len() - 1)
+        } else {
+            Position::default()
+        };
+
+        self.record(Edit::Insert {
+            at,
+            text: "\n".to_string(),
+        })
     }
 
-    pub fn insert_newline(&mut self, at: &Position) {
-        if at.y > self.len() as u16 {
-            return;
+    pub fn delete(&mut self, at: &Position) -> Result<(), DocumentError> {
+        if at.y >= self.len() {
+            return Ok(());
         }
 
-        self.dirty = true;
+        let row = &self.rows[at.y];
 
-        if at.y == self.len() as u16 {
-            self.rows.push(Row::default());
+        let text = if at.x >= row.len() {
+            if at.y < self.len() - 1 {
+                "\n".to_string()
+            } else {
+                return Ok(());
+            }
         } else {
-            let row = self
-                .rows
-                .get_mut(at.y as usize)
-                .unwrap()
-                .split(at.x as usize);
-            self.rows.insert((at.y + 1) as usize, row);
+            row.grapheme(at.x).unwrap_or_default().to_string()
+        };
+
+        self.record(Edit::Delete { at: *at, text })
+    }
+
+    pub fn clamp(&self, position: &Position) -> Position {
+        let y = cmp::min(position.y, self.len());
+        let x = match self.rows.get(y) {
+            Some(row) => cmp::min(position.x, row.len()),
+            None => 0,
+        };
+
+        Position::new(x, y)
+    }
+
+    pub fn text(&self, start: &Position, end: &Position) -> String {
+        let (start, end) = self.range(start, end);
+        let mut text = String::new();
+
+        for y in start.y..cmp::min(end.y + 1, self.len()) {
+            let row = &self.rows[y];
+            let from = if y == start.y { start.x } else { 0 };
+            let to = if y == end.y { end.x } else { row.len() };
+
+            text.push_str(row.slice(from, to));
+
+            if y < end.y {
+                text.push('\n');
+            }
         }
+
+        text
     }
 
-    pub fn delete(&mut self, at: &Position) {
-        if at.y >= self.len() as u16 {
-            return;
+    pub fn delete_range(
+        &mut self,
+        start: &Position,
+        end: &Position,
+    ) -> Result<String, DocumentError> {
+        let text = self.text(start, end);
+        let (start, _) = self.range(start, end);
+
+        if !text.is_empty() {
+            self.record_group(vec![Edit::Delete {
+                at: start,
+                text: text.clone(),
+            }])?;
         }
 
-        self.dirty = true;
+        Ok(text)
+    }
 
-        if at.x as usize == self.rows[at.y as usize].len() && (at.y as usize) < self.len() - 1 {
-            let next = self.rows.remove((at.y as usize) + 1);
-            let row = self.rows.get_mut(at.y as usize).unwrap();
-            row.append(&next);
+    pub fn insert_text(&mut self, text: &str, at: &Position) -> Result<Position, DocumentError> {
+        let at = if at.y < self.len() || self.len() == 0 {
+            self.clamp(at)
         } else {
-            let row = self.rows.get_mut(at.y as usize).unwrap();
-            row.delete(at.x as usize);
+            self.clamp(&Position::new(usize::MAX, self.len() - 1))
+        };
+
+        if text.is_empty() {
+            return Ok(at);
         }
+
+        self.record_group(vec![Edit::Insert {
+            at,
+            text: text.to_string(),
+        }])?;
+
+        Ok(match text.rsplit_once('\n') {
+            Some((head, tail)) => Position::new(
+                tail.graphemes(true).count(),
+                at.y + head.matches('\n').count() + 1,
+            ),
+            None => Position::new(at.x + text.graphemes(true).count(), at.y),
+        })
     }
 
-    pub fn save(&mut self) -> crossterm::Result<()> {
+    pub fn matching_bracket(&self, at: &Position) -> Option<Position> {
+        let row = self.rows.get(at.y)?;
+        let (open, close, forward) = match row.grapheme(at.x)? {
+            "(" => ("(", ")", true),
+            "[" => ("[", "]", true),
+            "{" => ("{", "}", true),
+            ")" => (")", "(", false),
+            "]" => ("]", "[", false),
+            "}" => ("}", "{", false),
+            _ => return None,
+        };
+
+        if !row.is_code(at.x) {
+            return None;
+        }
+
+        let mut depth = 0;
+        let rows: Box<dyn Iterator<Item = usize>> = if forward {
+            Box::new(at.y..cmp::min(self.len(), at.y + BRACKET_SCAN_ROWS))
+        } else {
+            Box::new((at.y.saturating_sub(BRACKET_SCAN_ROWS)..=at.y).rev())
+        };
+
+        for y in rows {
+            let row = &self.rows[y];
+            let graphemes = row.graphemes();
+            let columns: Box<dyn Iterator<Item = usize>> = match (forward, y == at.y) {
+                (true, true) => Box::new(at.x..graphemes.len()),
+                (true, false) => Box::new(0..graphemes.len()),
+                (false, true) => Box::new((0..=at.x).rev()),
+                (false, false) => Box::new((0..graphemes.len()).rev()),
+            };
+
+            for x in columns.filter(|x| row.is_code(*x)) {
+                if graphemes[x] == open {
+                    depth += 1;
+                } else if graphemes[x] == close {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return Some(Position::new(x, y));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn indent_lines(
+        &mut self,
+        start: usize,
+        end: usize,
+        unit: &str,
+    ) -> Result<(), DocumentError> {
+        let edits: Vec<Edit> = (start..cmp::min(end + 1, self.len()))
+            .filter(|y| self.rows[*y].len() > 0)
+            .map(|y| Edit::Insert {
+                at: Position::new(0, y),
+                text: unit.to_string(),
+            })
+            .collect();
+
+        if edits.is_empty() {
+            return Ok(());
+        }
+
+        self.record_group(edits)
+    }
+
+    pub fn dedent_lines(
+        &mut self,
+        start: usize,
+        end: usize,
+        width: usize,
+    ) -> Result<(), DocumentError> {
+        let edits: Vec<Edit> = (start..cmp::min(end + 1, self.len()))
+            .filter_map(|y| {
+                let row = self.rows[y].to_string();
+                let text = if row.starts_with('\t') {
+                    "\t".to_string()
+                } else {
+                    " ".repeat(row.chars().take(width).take_while(|ch| *ch == ' ').count())
+                };
+
+                (!text.is_empty()).then(|| Edit::Delete {
+                    at: Position::new(0, y),
+                    text,
+                })
+            })
+            .collect();
+
+        if edits.is_empty() {
+            return Ok(());
+        }
+
+        self.record_group(edits)
+    }
+
+    pub fn take_changes(&mut self) -> Vec<Edit> {
+        mem::take(&mut self.changes)
+    }
+
+    pub fn undo(&mut self) -> Option<Position> {
+        let group = self.history.undo()?;
+        let mut position = None;
+
+        for edit in group.iter().rev() {
+            position = Some(self.apply(&edit.inverse()));
+        }
+
+        position
+    }
+
+    pub fn redo(&mut self) -> Option<Position> {
+        let group = self.history.redo()?;
+        let mut position = None;
+
+        for edit in &group {
+            position = Some(self.apply(edit));
+        }
+
+        position
+    }
+
+    pub fn save(&mut self, backup: bool) -> Result<(), DocumentError> {
         if let Some(filename) = &self.filename {
-            let mut file = fs::File::create(filename)?;
+            if self.read_only {
+                return Err(DocumentError::ReadOnly(filename.clone()));
+            }
+
+            let path = fs::canonicalize(filename).unwrap_or_else(|_| PathBuf::from(filename));
+            let metadata = fs::metadata(&path).ok();
+            let directory = match path.parent() {
+                Some(directory) if !directory.as_os_str().is_empty() => directory,
+                _ => Path::new("."),
+            };
+            let directory_error = |error: io::Error| match error.kind() {
+                io::ErrorKind::PermissionDenied => {
+                    DocumentError::PermissionDenied(directory.display().to_string())
+                }
+                _ => DocumentError::from_io(filename, error),
+            };
+
+            if metadata.is_some() {
+                fs::OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .map_err(|error| DocumentError::from_io(filename, error))?;
+            }
+
+            if backup && metadata.is_some() {
+                let mut backup = path.clone().into_os_string();
+                backup.push("~");
+                fs::copy(&path, backup).map_err(directory_error)?;
+            }
+
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let temp = directory.join(format!(".{name}.{}.tmp", process::id()));
+
+            let result = match self.write_temp(&temp, metadata.as_ref()) {
+                Ok(true) => fs::rename(&temp, &path).map_err(directory_error),
+                Ok(false) => {
+                    let _ = fs::remove_file(&temp);
+                    self.write_in_place(&path)
+                        .map_err(|error| DocumentError::from_io(filename, error))
+                }
+                Err(error) => Err(directory_error(error)),
+            };
+
+            if let Err(error) = result {
+                let _ = fs::remove_file(&temp);
+                return Err(error);
+            }
 
-            for row in &self.rows {
-                file.write_all(row.as_bytes())?;
-                file.write_all(b"\n")?;
+            if let Ok(directory) = fs::File::open(directory) {
+                let _ = directory.sync_all();
             }
         }
 
@@ -101,13 +516,533 @@ impl Document {
         Ok(())
     }
 
-    pub fn find(&self, query: &str) -> Option<Position> {
+    fn write_temp(&self, temp: &Path, metadata: Option<&fs::Metadata>) -> io::Result<bool> {
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(temp)?;
+
+        if let Some(metadata) = metadata {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+
+                if let Err(error) =
+                    std::os::unix::fs::chown(temp, Some(metadata.uid()), Some(metadata.gid()))
+                {
+                    if error.kind() == io::ErrorKind::PermissionDenied {
+                        return Ok(false);
+                    }
+
+                    return Err(error);
+                }
+            }
+
+            fs::set_permissions(temp, metadata.permissions())?;
+        }
+
+        self.write_to(file)?;
+
+        Ok(true)
+    }
+
+    fn write_in_place(&self, path: &Path) -> io::Result<()> {
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+
+        self.write_to(file)
+    }
+
+    fn write_to(&self, file: fs::File) -> io::Result<()> {
+        let mut writer = BufWriter::new(file);
+
+        if self.bom {
+            writer.write_all("\u{feff}".as_bytes())?;
+        }
+
+        let last = self.len().saturating_sub(1);
+
         for (y, row) in self.rows.iter().enumerate() {
-            if let Some(x) = row.find(query) {
-                return Some(Position::new(x as u16, y as u16));
+            writer.write_all(row.as_bytes())?;
+
+            if y < last || !self.missing_final_newline {
+                writer.write_all(row.ending().as_bytes())?;
+            }
+        }
+
+        let file = writer.into_inner().map_err(|error| error.into_error())?;
+        file.sync_all()
+    }
+
+    pub fn find(
+        &self,
+        pattern: &Regex,
+        at: &Position,
+        direction: SearchDirection,
+    ) -> Option<Position> {
+        if self.rows.len() == 0 {
+            return None;
+        }
+
+        let len = self.len();
+        let start = cmp::min(at.y, len - 1);
+
+        for i in 0..=len {
+            let (y, x) = match direction {
+                SearchDirection::Forward => {
+                    let y = (start + i) % len;
+                    (y, if i == 0 { at.x } else { 0 })
+                }
+                SearchDirection::Backward => {
+                    let y = (start + len - i % len) % len;
+                    (y, if i == 0 { at.x } else { self.rows[y].len() })
+                }
+            };
+
+            if let Some(x) = self.rows[y].find(pattern, x, direction) {
+                return Some(Position::new(x, y));
             }
         }
 
         None
     }
+
+    pub fn replace(
+        &mut self,
+        pattern: &Regex,
+        replacement: &str,
+        at: &Position,
+    ) -> Result<Option<Position>, DocumentError> {
+        let Some((old, new)) = self
+            .rows
+            .get(at.y)
+            .and_then(|row| row.replacement(pattern, replacement, at.x))
+        else {
+            return Ok(None);
+        };
+
+        let at = self.clamp(at);
+        let end = Position::new(at.x + new.graphemes(true).count(), at.y);
+
+        self.record_group(vec![
+            Edit::Delete { at, text: old },
+            Edit::Insert { at, text: new },
+        ])?;
+
+        Ok(Some(end))
+    }
+
+    pub fn replace_all(
+        &mut self,
+        pattern: &Regex,
+        replacement: &str,
+        from: &Position,
+    ) -> Result<usize, DocumentError> {
+        let mut edits = Vec::new();
+
+        for y in (from.y..self.len()).rev() {
+            let at = if y == from.y { from.x } else { 0 };
+
+            for (x, old, new) in self.rows[y]
+                .replacements(pattern, replacement, at)
+                .into_iter()
+                .rev()
+            {
+                let at = Position::new(x, y);
+                edits.push(Edit::Delete { at, text: old });
+                edits.push(Edit::Insert { at, text: new });
+            }
+        }
+
+        let count = edits.len() / 2;
+
+        if count > 0 {
+            self.record_group(edits)?;
+        }
+
+        Ok(count)
+    }
+
+    fn range(&self, start: &Position, end: &Position) -> (Position, Position) {
+        let (start, end) = if (start.y, start.x) <= (end.y, end.x) {
+            (start, end)
+        } else {
+            (end, start)
+        };
+
+        let last = self.len().saturating_sub(1);
+        let clamp = |position: &Position| {
+            if position.y > last {
+                self.clamp(&Position::new(usize::MAX, last))
+            } else {
+                self.clamp(position)
+            }
+        };
+
+        (clamp(start), clamp(end))
+    }
+
+    fn check_writable(&self) -> Result<(), DocumentError> {
+        if self.read_only {
+            let filename = self.filename.clone().unwrap_or_default();
+            return Err(DocumentError::ReadOnly(filename));
+        }
+
+        Ok(())
+    }
+
+    fn record(&mut self, edit: Edit) -> Result<(), DocumentError> {
+        self.check_writable()?;
+        self.apply(&edit);
+        self.history.record(edit);
+
+        Ok(())
+    }
+
+    fn record_group(&mut self, edits: Vec<Edit>) -> Result<(), DocumentError> {
+        self.check_writable()?;
+
+        for edit in &edits {
+            self.apply(edit);
+        }
+
+        self.history.record_group(edits);
+
+        Ok(())
+    }
+
+    fn apply(&mut self, edit: &Edit) -> Position {
+        self.dirty = true;
+        self.changes.push(edit.clone());
+
+        let position = match edit {
+            Edit::Insert { at, text } => {
+                let mut position = *at;
+
+                for (i, line) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        self.split_row(&position);
+                        position = Position::new(0, position.y + 1);
+                    }
+
+                    if !line.is_empty() {
+                        self.insert_str(line, &position);
+                        position.x += line.graphemes(true).count();
+                    }
+                }
+
+                position
+            }
+            Edit::Delete { at, text } => {
+                for _ in text.graphemes(true) {
+                    self.delete_char(at);
+                }
+
+                *at
+            }
+        };
+
+        let at = match edit {
+            Edit::Insert { at, .. } | Edit::Delete { at, .. } => at,
+        };
+        self.highlight(at.y, position.y);
+
+        position
+    }
+
+    fn highlight(&mut self, from: usize, until: usize) {
+        let opts = self.file_type.highlighting_options();
+        let mut state = if from == 0 {
+            State::default()
+        } else {
+            self.rows[from - 1].state()
+        };
+
+        for y in from..self.len() {
+            let row = &mut self.rows[y];
+            let previous = row.state();
+            state = row.highlight(opts, state);
+
+            if y >= until && state == previous {
+                break;
+            }
+        }
+    }
+
+    fn insert_str(&mut self, string: &str, at: &Position) {
+        if let Some(row) = self.rows.get_mut(at.y) {
+            let at = cmp::min(at.x, row.len());
+            row.insert_str(string, at);
+        } else {
+            let mut row = Row::from(string);
+            row.set_ending(self.line_ending);
+            self.rows.push(row);
+        }
+    }
+
+    fn split_row(&mut self, at: &Position) {
+        if at.y >= self.len() {
+            let mut row = Row::default();
+            row.set_ending(self.line_ending);
+            self.rows.push(row);
+        } else {
+            let row = self.rows[at.y].split(at.x);
+            self.rows[at.y].set_ending(self.line_ending);
+            self.rows.insert(at.y + 1, row);
+        }
+    }
+
+    fn delete_char(&mut self, at: &Position) {
+        if at.y >= self.len() {
+            return;
+        }
+
+        if at.x == self.rows[at.y].len() && at.y < self.len() - 1 {
+            let next = self.rows.remove((at.y) + 1);
+            self.rows[at.y].append(&next);
+        } else {
+            self.rows[at.y].delete(at.x);
+        }
+    }
+}
+
+fn sanitize(contents: &str) -> String {
+    contents
+        .chars()
+        .map(|ch| match ch {
+            '\n' | '\r' | '\t' => ch,
+            ch if ch.is_control() => char::REPLACEMENT_CHARACTER,
+            ch => ch,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(contents: &str) -> Document {
+        let mut document = Document::default();
+        document.load(contents);
+        document
+    }
+
+    fn temp_file(name: &str, bytes: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("trusty-{}-{name}", process::id()));
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn round_trip(name: &str, bytes: &[u8]) -> Vec<u8> {
+        let filename = temp_file(name, bytes);
+        let Ok(mut document) = Document::open(&filename) else {
+            panic!("{filename} could not be opened");
+        };
+        assert!(document.save(false).is_ok());
+
+        let saved = fs::read(&filename).unwrap();
+        fs::remove_file(&filename).unwrap();
+        saved
+    }
+
+    fn document_with(filename: &str, contents: &str) -> Document {
+        let mut document = document(contents);
+        document.set_filename(filename.to_string());
+        document
+    }
+
+    fn contents(document: &Document) -> Vec<String> {
+        document.rows.iter().map(Row::to_string).collect()
+    }
+
+    #[test]
+    fn undo_and_redo_restore_text_and_cursor() {
+        let mut document = document("ab\ncd\n");
+        document.insert('x', &Position::new(1, 0)).unwrap();
+        document.insert('y', &Position::new(2, 0)).unwrap();
+        document.insert_newline(&Position::new(3, 0)).unwrap();
+        document.delete(&Position::new(0, 2)).unwrap();
+
+        assert_eq!(contents(&document), ["axy", "b", "d"]);
+
+        let position = document.undo().unwrap();
+        assert_eq!((position.x, position.y), (1, 2));
+        assert_eq!(contents(&document), ["axy", "b", "cd"]);
+
+        document.undo();
+        assert_eq!(contents(&document), ["axyb", "cd"]);
+
+        let position = document.undo().unwrap();
+        assert_eq!((position.x, position.y), (1, 0));
+        assert_eq!(contents(&document), ["ab", "cd"]);
+        assert!(document.undo().is_none());
+
+        document.redo();
+        document.redo();
+        document.redo();
+        assert_eq!(contents(&document), ["axy", "b", "d"]);
+    }
+
+    #[test]
+    fn replace_handles_overlapping_matches() {
+        let mut document = document("aaaa\n");
+        let pattern = Regex::new("aa").unwrap();
+
+        let found = document
+            .find(&pattern, &Position::new(1, 0), SearchDirection::Forward)
+            .unwrap();
+        assert_eq!((found.x, found.y), (1, 0));
+
+        let end = document.replace(&pattern, "b", &found).unwrap().unwrap();
+        assert_eq!((end.x, end.y), (2, 0));
+        assert_eq!(contents(&document), ["aba"]);
+        assert!(document.replace(&pattern, "b", &end).unwrap().is_none());
+    }
+
+    #[test]
+    fn replace_all_starts_at_the_cursor() {
+        let mut document = document("aaaa\naaaa\n");
+        let pattern = Regex::new("aa").unwrap();
+
+        assert_eq!(
+            document
+                .replace_all(&pattern, "b", &Position::new(1, 0))
+                .unwrap(),
+            3
+        );
+        assert_eq!(contents(&document), ["aba", "bb"]);
+    }
+
+    #[test]
+    fn save_preserves_line_endings_bom_and_final_newline() {
+        for (name, bytes) in [
+            ("lf", &b"one\ntwo\n"[..]),
+            ("crlf", b"one\r\ntwo\r\n"),
+            ("mixed", b"one\r\ntwo\nthree\r\n"),
+            ("no-newline", b"one\r\ntwo"),
+            ("bom", "\u{feff}one\ntwo".as_bytes()),
+            ("blank-lines", b"\n\n"),
+            ("empty", b""),
+        ] {
+            assert_eq!(round_trip(name, bytes), bytes, "{name}");
+        }
+    }
+
+    #[test]
+    fn new_rows_use_the_detected_line_ending() {
+        let mut document = document("one\r\ntwo\r\n");
+        document.insert_newline(&Position::new(3, 0)).unwrap();
+
+        assert!(document
+            .rows
+            .iter()
+            .all(|row| row.ending() == LineEnding::Crlf));
+        assert!(document.line_ending() == Some(LineEnding::Crlf));
+    }
+
+    #[test]
+    fn read_only_documents_reject_edits() {
+        let filename = temp_file("binary", b"a\0b\nab\n");
+        let Ok(mut document) = Document::open(&filename) else {
+            panic!("{filename} could not be opened");
+        };
+        fs::remove_file(&filename).unwrap();
+        let pattern = Regex::new("b").unwrap();
+        let before = contents(&document);
+
+        assert!(document.is_read_only());
+        assert!(matches!(
+            document.insert('x', &Position::default()),
+            Err(DocumentError::ReadOnly(_))
+        ));
+        assert!(document.delete(&Position::default()).is_err());
+        assert!(document.insert_text("x", &Position::default()).is_err());
+        assert!(document
+            .replace_all(&pattern, "c", &Position::default())
+            .is_err());
+        assert!(document.set_line_ending(LineEnding::Crlf).is_err());
+        assert!(document.save(false).is_err());
+        assert_eq!(contents(&document), before);
+        assert!(!document.is_dirty());
+    }
+
+    #[test]
+    fn changes_report_edits_away_from_the_cursor() {
+        let mut document = document("one\ntwo\nthree\n");
+        document
+            .insert_text("x\ny\n", &Position::new(0, 2))
+            .unwrap();
+        document.take_changes();
+
+        document.undo();
+        let other_pane = document
+            .take_changes()
+            .iter()
+            .fold(Position::new(3, 4), |position, change| {
+                change.shift(&position)
+            });
+
+        assert_eq!((other_pane.x, other_pane.y), (3, 2));
+        assert!(document.take_changes().is_empty());
+    }
+
+    #[test]
+    fn deleting_across_rows_rehighlights_the_merged_row() {
+        let mut document = document_with("test.rs", "a\n/*\nb\n");
+        assert!(!document.rows[2].is_code(0));
+
+        document
+            .delete_range(&Position::new(1, 0), &Position::new(2, 1))
+            .unwrap();
+
+        assert_eq!(contents(&document), ["a", "b"]);
+        assert!(document.rows[1].is_code(0));
+    }
+
+    #[test]
+    fn strings_span_rows() {
+        let code = |document: &Document, y: usize| -> Vec<bool> {
+            (0..document.rows[y].len())
+                .map(|x| document.rows[y].is_code(x))
+                .collect()
+        };
+
+        let document = document_with("test.rs", "let s = \"a\nb \\\" c\";\nx\n");
+        assert_eq!(
+            code(&document, 1),
+            [[false; 7].as_slice(), &[true]].concat()
+        );
+        assert!(code(&document, 2).iter().all(|code| *code));
+
+        let document = document_with("test.py", "s = '''\nit's\n'''\nx = 1\n");
+        assert!(code(&document, 1).iter().all(|code| !code));
+        assert!(code(&document, 2).iter().all(|code| !code));
+        assert!(code(&document, 3).iter().all(|code| *code));
+
+        let document = document_with("test.toml", "a = '''\nC:\\'''\nb = 1\n");
+        assert!(code(&document, 1).iter().all(|code| !code));
+        assert!(code(&document, 2).iter().all(|code| *code));
+    }
+
+    #[test]
+    fn bracket_matching_skips_multi_line_strings() {
+        let document = document_with("test.rs", "f(\"\n)\n\", 1)\n");
+        let partner = document.matching_bracket(&Position::new(1, 0)).unwrap();
+
+        assert_eq!((partner.x, partner.y), (4, 2));
+    }
+
+    #[test]
+    fn undo_reverts_a_multi_line_group() {
+        let mut document = document("one\ntwo\nthree\n");
+        document
+            .delete_range(&Position::new(1, 0), &Position::new(2, 2))
+            .unwrap();
+        assert_eq!(contents(&document), ["oree"]);
+
+        document.undo();
+        assert_eq!(contents(&document), ["one", "two", "three"]);
+    }
 }
@@ -0,0 +1,33 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum DocumentError {
+    PermissionDenied(String),
+    IsDirectory(String),
+    ReadOnly(String),
+    Io(String, io::Error),
+}
+
+impl DocumentError {
+    pub fn from_io(filename: &str, error: io::Error) -> DocumentError {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => {
+                DocumentError::PermissionDenied(filename.to_string())
+            }
+            _ => DocumentError::Io(filename.to_string(), error),
+        }
+    }
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::PermissionDenied(filename) => {
+                write!(f, "Permission denied: {filename}")
+            }
+            DocumentError::IsDirectory(filename) => write!(f, "{filename} is a directory"),
+            DocumentError::ReadOnly(filename) => write!(f, "{filename} is read-only"),
+            DocumentError::Io(filename, error) => write!(f, "{filename}: {error}"),
+        }
+    }
+}
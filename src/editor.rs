@@ -1,278 +1,2217 @@
+use crate::fuzzy;
+use crate::highlighting;
+use crate::keymap::{self, Lookup};
+use crate::vi::{self, Action, Mode, Motion, Operator, Parse};
+use crate::window::{Arrangement, Layout, Pane, Rect, Split};
+use crate::wrap;
+use crate::Buffer;
+use crate::Command;
+use crate::Config;
 use crate::Document;
+use crate::DocumentError;
+use crate::Indent;
+use crate::LineEnding;
+use crate::LineNumbers;
 use crate::Position;
+use crate::Register;
+use crate::Row;
+use crate::Screen;
 use crate::StatusMessage;
 use crate::Terminal;
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
-use crossterm::style::Color;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use regex::Regex;
+use std::cmp::{self, Reverse};
+use std::env;
 use std::time::{Duration, Instant};
-use std::{cmp, env};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-const FG_COLOR: Color = Color::White;
-const BG_COLOR: Color = Color::Black;
-const STATUS_BAR_FG_COLOR: Color = Color::Black;
-const STATUS_BAR_BG_COLOR: Color = Color::White;
-const TILDES_COLOR: Color = Color::DarkGrey;
-const TAB_SIZE: usize = 4;
-const STATUS_MESSAGE_DURATION: u64 = 5;
+const RESIZE_STEP: i16 = 5;
+const PALETTE_HEIGHT: usize = 10;
+const WHEEL_STEP: usize = 3;
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
 
 pub struct Editor {
     running: bool,
     cursor_position: Position,
-    document: Document,
+    buffers: Vec<Buffer>,
+    current: usize,
     offset: Position,
+    panes: Vec<Pane>,
+    layout: Layout,
+    focus: usize,
     status_message: StatusMessage,
+    search_query: Option<Regex>,
+    config: Config,
+    pending_keys: Vec<KeyEvent>,
+    candidates: Vec<String>,
+    selected_candidate: usize,
+    mode: Mode,
+    vi_keys: Vec<char>,
+    vi_recording: Option<Vec<KeyEvent>>,
+    last_change: Vec<KeyEvent>,
+    anchor: Option<Position>,
+    register: Register,
+    last_click: Option<(Instant, usize, Position)>,
+}
+
+impl Default for Editor {
+    fn default() -> Editor {
+        let args: Vec<String> = env::args().collect();
+
+        let mut config = Config::default();
+        let status_message = match config.load() {
+            Ok(()) => {
+                "[HELP] CTRL-Q = quit | CTRL-S = save | CTRL-F = find | CTRL-Z = undo | CTRL-Y = redo"
+                    .to_string()
+            }
+            Err(error) => format!("[ERROR] {error}"),
+        };
+
+        let vi_mode = config.vi_mode;
+
+        let mut editor = Editor {
+            running: true,
+            cursor_position: Position::default(),
+            buffers: Vec::new(),
+            current: 0,
+            offset: Position::default(),
+            panes: vec![Pane::default()],
+            layout: Layout::Pane(0),
+            focus: 0,
+            status_message: StatusMessage::from(status_message),
+            search_query: None,
+            config,
+            pending_keys: Vec::new(),
+            candidates: Vec::new(),
+            selected_candidate: 0,
+            mode: if vi_mode { Mode::Normal } else { Mode::Insert },
+            vi_keys: Vec::new(),
+            vi_recording: None,
+            last_change: Vec::new(),
+            anchor: None,
+            register: Register::default(),
+            last_click: None,
+        };
+
+        for filename in args.iter().skip(1) {
+            editor.open(filename);
+        }
+
+        if editor.buffers.is_empty() {
+            let mut document = Document::default();
+            document.set_tab_size(editor.config.tab_size);
+            editor.buffers.push(Buffer::from(document));
+        }
+
+        editor.switch_buffer(0);
+
+        editor
+    }
 }
 
-impl Default for Editor {
-    fn default() -> Editor {
-        let args: Vec<String> = env::args().collect();
+impl Editor {
+    pub fn run(&mut self) -> crossterm::Result<()> {
+        Terminal::enter_alternate_screen()?;
+        Terminal::enable_raw_mode()?;
+        Terminal::enable_bracketed_paste()?;
+        Terminal::enable_mouse_capture()?;
+
+        let result = self.event_loop();
+
+        Terminal::disable_mouse_capture()?;
+        Terminal::disable_bracketed_paste()?;
+        Terminal::disable_raw_mode()?;
+        Terminal::leave_alternate_screen()?;
+
+        result
+    }
+
+    fn event_loop(&mut self) -> crossterm::Result<()> {
+        while self.running {
+            self.refresh_screen()?;
+            self.process_event()?;
+        }
+
+        Ok(())
+    }
+
+    fn refresh_screen(&mut self) -> crossterm::Result<()> {
+        let viewport = self.viewport()?;
+
+        let cursor = if self.config.soft_wrap {
+            let document = self.document();
+            let (segment, column) = wrap::locate(document, &self.cursor_position, viewport.width);
+
+            wrap::distance(
+                document,
+                (self.offset.y, self.offset.x),
+                (self.cursor_position.y, segment),
+                viewport.width,
+                viewport.height,
+            )
+            .filter(|_| column < viewport.width)
+            .map(|line| Position::new(viewport.x + column, viewport.y + line))
+        } else {
+            let column = self.cursor_column();
+            let visible = (self.offset.y..self.offset.y + viewport.height)
+                .contains(&self.cursor_position.y)
+                && (self.offset.x..self.offset.x + viewport.width).contains(&column);
+
+            visible.then(|| {
+                Position::new(
+                    viewport.x + column - self.offset.x,
+                    viewport.y + self.cursor_position.y - self.offset.y,
+                )
+            })
+        };
+
+        self.render(cursor)
+    }
+
+    fn render(&mut self, cursor: Option<Position>) -> crossterm::Result<()> {
+        self.sync_pane();
+
+        let size = Terminal::size()?;
+        let mut screen = Screen::new(
+            size.width as usize,
+            size.height as usize + 2,
+            self.config.fg_color,
+            self.config.bg_color,
+        );
+
+        let arrangement = self.arrange()?;
+
+        for (index, rect) in &arrangement.panes {
+            self.draw_rows(&mut screen, *index, rect);
+            self.draw_status_bar(&mut screen, *index, rect);
+        }
+
+        self.draw_separators(&mut screen, &arrangement.separators);
+        self.draw_candidates(&mut screen);
+        self.draw_status_message(&mut screen);
+
+        Terminal::draw(screen, cursor)
+    }
+
+    fn process_event(&mut self) -> crossterm::Result<()> {
+        match Terminal::read_event()? {
+            Event::Key(key) => self.handle_key(key)?,
+            Event::Paste(text) => self.paste_text(&text),
+            Event::Resize(..) => self.resize()?,
+            Event::Mouse(event) => match event.kind {
+                MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                    return self.scroll_wheel(event);
+                }
+                MouseEventKind::Down(MouseButton::Left)
+                | MouseEventKind::Drag(MouseButton::Left) => {
+                    self.handle_mouse(event)?;
+                }
+                _ => return Ok(()),
+            },
+            _ => {}
+        }
+
+        self.sync_panes();
+        self.scroll()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> crossterm::Result<()> {
+        if !self.config.vi_mode {
+            return self.handle_mapped_key(key);
+        }
+
+        match (self.mode, key) {
+            (
+                Mode::Insert,
+                KeyEvent {
+                    code: KeyCode::Esc, ..
+                },
+            ) if self.pending_keys.is_empty() => {
+                if let Some(mut keys) = self.vi_recording.take() {
+                    keys.push(key);
+                    self.last_change = keys;
+                }
+
+                self.anchor = None;
+                self.mode = Mode::Normal;
+                self.cursor_position.x = self.cursor_position.x.saturating_sub(1);
+                Ok(())
+            }
+            (Mode::Insert, _) => {
+                let edits = self.pending_keys.is_empty()
+                    && matches!(
+                        key,
+                        KeyEvent {
+                            code: KeyCode::Char(_),
+                            modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                            ..
+                        } | KeyEvent {
+                            code: KeyCode::Enter
+                                | KeyCode::Tab
+                                | KeyCode::Backspace
+                                | KeyCode::Delete,
+                            modifiers: KeyModifiers::NONE,
+                            ..
+                        }
+                    );
+
+                if let Some(keys) = self.vi_recording.as_mut().filter(|_| edits) {
+                    keys.push(key);
+                }
+
+                self.handle_mapped_key(key)
+            }
+            (
+                _,
+                KeyEvent {
+                    code: KeyCode::Char(ch),
+                    modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                    ..
+                },
+            ) if self.pending_keys.is_empty() => self.handle_vi_key(ch),
+            (
+                _,
+                KeyEvent {
+                    code: KeyCode::Esc, ..
+                },
+            ) if self.pending_keys.is_empty() => {
+                self.vi_keys.clear();
+                self.anchor = None;
+                self.mode = Mode::Normal;
+                Ok(())
+            }
+            _ => self.handle_mapped_key(key),
+        }
+    }
+
+    fn handle_mapped_key(&mut self, key: KeyEvent) -> crossterm::Result<()> {
+        self.pending_keys.push(keymap::normalize(key));
+
+        match self.config.keymap.lookup(&self.pending_keys) {
+            Lookup::Command(command) => {
+                if self.pending_keys.len() > 1 {
+                    self.status_message = StatusMessage::default();
+                }
+
+                self.pending_keys.clear();
+
+                let command = match (self.mode, command) {
+                    (Mode::Insert, command) => command,
+                    (_, Command::InsertNewline) => Command::MoveDown,
+                    (_, Command::Backspace) => Command::MoveLeft,
+                    (_, Command::InsertTab) => return Ok(()),
+                    (_, command) => command,
+                };
+
+                self.execute(command)?;
+            }
+            Lookup::Prefix => {
+                let continuations = self
+                    .config
+                    .keymap
+                    .continuations(&self.pending_keys)
+                    .iter()
+                    .map(|(keys, command)| format!("{keys} = {}", command.name()))
+                    .collect::<Vec<String>>()
+                    .join(" | ");
+
+                self.status_message = StatusMessage::from(format!(
+                    "[HELP] {} ... {continuations}",
+                    keymap::format_keys(&self.pending_keys)
+                ));
+            }
+            Lookup::Unbound => {
+                match key {
+                    KeyEvent {
+                        code: KeyCode::Char(ch),
+                        modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                        ..
+                    } if self.pending_keys.len() == 1 => {
+                        self.delete_selection();
+
+                        let position = self.cursor_position;
+
+                        if self
+                            .edit(|document| document.insert(ch, &position))
+                            .is_some()
+                        {
+                            self.move_cursor(KeyCode::Right)?;
+                        }
+                    }
+                    KeyEvent {
+                        code: KeyCode::Esc, ..
+                    } if self.pending_keys.len() > 1 => {
+                        self.status_message = StatusMessage::default();
+                    }
+                    _ if self.pending_keys.len() > 1 => {
+                        self.status_message = StatusMessage::from(format!(
+                            "[WARNING] {} is not bound",
+                            keymap::format_keys(&self.pending_keys)
+                        ));
+                    }
+                    _ => {}
+                }
+
+                self.pending_keys.clear();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_vi_key(&mut self, ch: char) -> crossterm::Result<()> {
+        self.vi_keys.push(ch);
+
+        if self.mode == Mode::Visual {
+            let operator = match self.vi_keys[..] {
+                ['d' | 'x'] => Some(Operator::Delete),
+                ['c'] => Some(Operator::Change),
+                ['y'] => Some(Operator::Yank),
+                _ => None,
+            };
+
+            if let Some(operator) = operator {
+                self.vi_keys.clear();
+
+                if let Some((start, end)) = self.selection() {
+                    self.set_mode(Mode::Normal);
+                    self.operate(operator, start, end)?;
+                }
+
+                return Ok(());
+            }
+        }
+
+        let (count, action) = match vi::parse(&self.vi_keys) {
+            Parse::Incomplete => return Ok(()),
+            Parse::Invalid => {
+                self.vi_keys.clear();
+                return Ok(());
+            }
+            Parse::Complete(count, action) => (count, action),
+        };
+
+        let keys: Vec<KeyEvent> = self
+            .vi_keys
+            .drain(..)
+            .map(|ch| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))
+            .collect();
+
+        if self.mode == Mode::Normal
+            && matches!(
+                action,
+                Action::Operate(Operator::Delete | Operator::Change, _)
+                    | Action::OperateLines(Operator::Delete | Operator::Change)
+                    | Action::Insert(_)
+                    | Action::DeleteChar
+                    | Action::Paste(_)
+            )
+        {
+            self.vi_recording = Some(keys);
+        }
+
+        self.run_vi_action(count, action)?;
+
+        if self.mode != Mode::Insert {
+            if let Some(keys) = self.vi_recording.take() {
+                self.last_change = keys;
+            }
+
+            if self.mode == Mode::Normal {
+                let len = self
+                    .document()
+                    .row(self.cursor_position.y)
+                    .map_or(0, Row::len);
+                self.cursor_position.x = self.cursor_position.x.min(len.saturating_sub(1));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_vi_action(&mut self, count: Option<usize>, action: Action) -> crossterm::Result<()> {
+        let position = self.cursor_position;
+
+        match action {
+            Action::Move(motion) => {
+                self.cursor_position = motion.target(self.document(), position, count);
+            }
+            Action::Operate(operator, motion) => {
+                let motion = match (operator, motion) {
+                    (Operator::Change, Motion::WordForward)
+                        if self
+                            .document()
+                            .row(position.y)
+                            .and_then(|row| row.grapheme(position.x))
+                            .is_some_and(|grapheme| !grapheme.trim().is_empty()) =>
+                    {
+                        Motion::WordEnd
+                    }
+                    _ => motion,
+                };
+                let target = motion.target(self.document(), position, count);
+
+                if motion.is_linewise() {
+                    self.operate_lines(
+                        operator,
+                        position.y.min(target.y),
+                        position.y.max(target.y),
+                    )?;
+                } else {
+                    let (start, mut end) = if (target.y, target.x) < (position.y, position.x) {
+                        (target, position)
+                    } else {
+                        (position, target)
+                    };
+
+                    if motion.is_inclusive() {
+                        end.x += 1;
+                    }
+
+                    self.operate(operator, start, end)?;
+                }
+            }
+            Action::OperateLines(operator) => {
+                let last = position.y.saturating_add(count.unwrap_or(1) - 1);
+                self.operate_lines(operator, position.y, last)?;
+            }
+            Action::Insert(command) => {
+                let len = self.document().row(position.y).map_or(0, Row::len);
+
+                match command {
+                    'a' => self.cursor_position.x = (position.x + 1).min(len),
+                    'I' => {
+                        self.cursor_position.x = vi::first_non_blank(self.document(), position.y)
+                    }
+                    'A' => self.cursor_position.x = len,
+                    'o' => {
+                        self.cursor_position = Position::new(len, position.y);
+                        self.insert_newline();
+                    }
+                    'O' => {
+                        let indent = if self.config.auto_indent {
+                            self.leading_whitespace(position.y, len)
+                        } else {
+                            String::new()
+                        };
+
+                        let text = format!("{indent}\n");
+
+                        if self
+                            .edit(|document| {
+                                document.insert_text(&text, &Position::new(0, position.y))
+                            })
+                            .is_some()
+                        {
+                            self.cursor_position =
+                                Position::new(indent.chars().count(), position.y);
+                        }
+                    }
+                    _ => {}
+                }
+
+                self.set_mode(Mode::Insert);
+            }
+            Action::Visual => {
+                if self.mode == Mode::Visual {
+                    self.set_mode(Mode::Normal);
+                } else {
+                    self.anchor = Some(position);
+                    self.mode = Mode::Visual;
+                }
+            }
+            Action::DeleteChar => {
+                let end = Motion::Right.target(self.document(), position, count);
+                self.operate(Operator::Delete, position, end)?;
+            }
+            Action::Paste(before) => {
+                for _ in 0..count.unwrap_or(1) {
+                    self.paste(before);
+                }
+            }
+            Action::Undo => {
+                for _ in 0..count.unwrap_or(1) {
+                    match self.document_mut().undo() {
+                        Some(position) => self.cursor_position = position,
+                        None => break,
+                    }
+                }
+            }
+            Action::Repeat => {
+                let keys = self.last_change.clone();
+
+                for _ in 0..count.unwrap_or(1) {
+                    for key in &keys {
+                        self.handle_key(*key)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn operate(
+        &mut self,
+        operator: Operator,
+        start: Position,
+        end: Position,
+    ) -> crossterm::Result<()> {
+        let text = self.document().text(&start, &end);
+
+        if operator != Operator::Yank
+            && self
+                .edit(|document| document.delete_range(&start, &end))
+                .is_none()
+        {
+            return Ok(());
+        }
+
+        if !text.is_empty() {
+            self.set_register(text, false)?;
+        }
+
+        if operator == Operator::Change {
+            self.set_mode(Mode::Insert);
+        }
+
+        self.cursor_position = self.document().clamp(&start);
+
+        Ok(())
+    }
+
+    fn operate_lines(
+        &mut self,
+        operator: Operator,
+        first: usize,
+        last: usize,
+    ) -> crossterm::Result<()> {
+        let len = self.document().len();
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        let last = last.min(len - 1);
+        let start = Position::new(0, first);
+        let end = Position::new(usize::MAX, last);
+
+        let text = format!("{}\n", self.document().text(&start, &end));
+
+        match operator {
+            Operator::Yank => self.cursor_position.y = first,
+            Operator::Delete => {
+                let (from, to) = if last + 1 < len {
+                    (start, Position::new(0, last + 1))
+                } else if first > 0 {
+                    (Position::new(usize::MAX, first - 1), end)
+                } else {
+                    (start, end)
+                };
+
+                if self
+                    .edit(|document| document.delete_range(&from, &to))
+                    .is_none()
+                {
+                    return Ok(());
+                }
+
+                let y = first.min(self.document().len().saturating_sub(1));
+                self.cursor_position = Position::new(vi::first_non_blank(self.document(), y), y);
+            }
+            Operator::Change => {
+                if self
+                    .edit(|document| document.delete_range(&start, &end))
+                    .is_none()
+                {
+                    return Ok(());
+                }
+
+                self.cursor_position = start;
+                self.set_mode(Mode::Insert);
+            }
+        }
+
+        self.set_register(text, true)
+    }
+
+    fn set_register(&mut self, text: String, linewise: bool) -> crossterm::Result<()> {
+        if self.config.osc52_clipboard {
+            Terminal::set_clipboard(&text)?;
+        }
+
+        self.register = Register { text, linewise };
+
+        Ok(())
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some((start, end)) => {
+                if self
+                    .edit(|document| document.delete_range(&start, &end))
+                    .is_none()
+                {
+                    return false;
+                }
+
+                self.anchor = None;
+                self.cursor_position = self.document().clamp(&start);
+
+                if self.mode == Mode::Visual {
+                    self.set_mode(Mode::Normal);
+                    self.clamp_normal();
+                }
+
+                start != end
+            }
+            None => false,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> crossterm::Result<()> {
+        let arrangement = self.arrange()?;
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let (index, rect) = match pane_at(&arrangement, event.column, event.row) {
+                    Some(pane) => pane,
+                    None => return Ok(()),
+                };
+
+                if index != self.focus {
+                    self.focus_pane(index);
+                }
+
+                let area = self.text_area(index, &rect);
+                let position = self.position_at(&area, event.column, event.row);
+                let double = matches!(
+                    self.last_click,
+                    Some((time, pane, last)) if pane == index
+                        && last == position
+                        && time.elapsed() < DOUBLE_CLICK
+                );
+
+                self.last_click = Some((Instant::now(), index, position));
+                self.cursor_position = position;
+                self.anchor = None;
+
+                if self.mode == Mode::Visual {
+                    self.mode = Mode::Normal;
+                }
+
+                if double {
+                    self.last_click = None;
+                    self.select_word();
+                } else if self.mode == Mode::Normal {
+                    self.clamp_normal();
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                let area = match arrangement.rect(self.focus) {
+                    Some(rect) => self.text_area(self.focus, &rect),
+                    None => return Ok(()),
+                };
+
+                if self.anchor.is_none() {
+                    self.anchor = Some(self.cursor_position);
+                }
+
+                if self.mode == Mode::Normal {
+                    self.mode = Mode::Visual;
+                }
+
+                self.cursor_position = self.position_at(&area, event.column, event.row);
+
+                if self.mode == Mode::Visual {
+                    self.clamp_normal();
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn scroll_wheel(&mut self, event: MouseEvent) -> crossterm::Result<()> {
+        let (index, rect) = match pane_at(&self.arrange()?, event.column, event.row) {
+            Some(pane) => pane,
+            None => return Ok(()),
+        };
+
+        self.sync_pane();
+
+        let width = self.text_area(index, &rect).width;
+        let pane = &mut self.panes[index];
+        let document = &self.buffers[pane.buffer].document;
+        let last = document.len().saturating_sub(1);
+
+        if self.config.soft_wrap {
+            let mut line = (pane.offset.y, pane.offset.x);
+
+            for _ in 0..WHEEL_STEP {
+                line = match event.kind {
+                    MouseEventKind::ScrollUp => wrap::previous(document, line, width),
+                    _ => wrap::next(document, line, width).filter(|(y, _)| *y <= last),
+                }
+                .unwrap_or(line);
+            }
+
+            pane.offset = Position::new(line.1, line.0);
+        } else {
+            pane.offset.y = match event.kind {
+                MouseEventKind::ScrollUp => pane.offset.y.saturating_sub(WHEEL_STEP),
+                _ => (pane.offset.y + WHEEL_STEP).min(last),
+            };
+        }
+
+        if index == self.focus {
+            self.offset = pane.offset;
+        }
+
+        Ok(())
+    }
+
+    fn position_at(&self, rect: &Rect, column: u16, row: u16) -> Position {
+        if self.config.soft_wrap {
+            let document = self.document();
+            let column = (column as usize).saturating_sub(rect.x);
+            let mut line = (self.offset.y, self.offset.x);
+
+            for _ in 0..(row as usize)
+                .saturating_sub(rect.y)
+                .min(rect.height.saturating_sub(1))
+            {
+                match wrap::next(document, line, rect.width) {
+                    Some(next) => line = next,
+                    None => break,
+                }
+            }
+
+            let (y, segment) = line;
+            let x = wrap::index(document, y, segment, column, rect.width);
+
+            return document.clamp(&Position::new(x, y));
+        }
+
+        let y = self.offset.y
+            + (row as usize)
+                .saturating_sub(rect.y)
+                .min(rect.height.saturating_sub(1));
+        let column = self.offset.x + (column as usize).saturating_sub(rect.x);
+
+        match self.document().row(y) {
+            Some(row) => Position::new(row.index(column, self.document().tab_size()), y),
+            None => self.document().clamp(&Position::new(0, y)),
+        }
+    }
+
+    fn select_word(&mut self) {
+        let position = self.cursor_position;
+        let (start, end) = match self.document().row(position.y) {
+            Some(row) => row.word_bounds(position.x),
+            None => return,
+        };
+
+        if start == end {
+            return;
+        }
+
+        self.anchor = Some(Position::new(start, position.y));
+        self.cursor_position = Position::new(end, position.y);
+
+        if self.mode == Mode::Normal {
+            self.mode = Mode::Visual;
+            self.cursor_position.x -= 1;
+        }
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        if self.mode == Mode::Visual && mode != Mode::Visual {
+            self.anchor = None;
+        }
+
+        self.mode = mode;
+    }
+
+    fn clamp_normal(&mut self) {
+        let len = self
+            .document()
+            .row(self.cursor_position.y)
+            .map_or(0, |row| row.len());
+
+        self.cursor_position.x = self.cursor_position.x.min(len.saturating_sub(1));
+    }
+
+    fn paste_text(&mut self, text: &str) {
+        self.delete_selection();
+
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let position = self.cursor_position;
+
+        if let Some(end) = self.edit(|document| document.insert_text(&text, &position)) {
+            self.cursor_position = end;
+
+            if self.mode == Mode::Normal {
+                self.cursor_position.x = self.cursor_position.x.saturating_sub(1);
+            }
+        }
+    }
+
+    fn select(&mut self, direction: KeyCode) -> crossterm::Result<()> {
+        if self.anchor.is_none() {
+            self.anchor = Some(self.cursor_position);
+        }
+
+        self.move_cursor(direction)
+    }
+
+    fn deselect_all(&mut self) {
+        self.anchor = None;
+
+        if self.mode == Mode::Visual {
+            self.mode = Mode::Normal;
+        }
+    }
+
+    fn deselect(&mut self) {
+        if self.mode != Mode::Visual {
+            self.anchor = None;
+        }
+    }
+
+    fn paste(&mut self, before: bool) {
+        let text = self.register.text.clone();
+        let position = self.cursor_position;
+
+        if self.register.linewise {
+            let len = self.document().len();
+            let y = if before { position.y } else { position.y + 1 };
+
+            let (text, at) = if y < len || len == 0 {
+                (text, Position::new(0, y))
+            } else {
+                let text = format!("\n{}", text.strip_suffix('\n').unwrap_or(&text));
+                (text, Position::new(usize::MAX, len - 1))
+            };
+
+            if self
+                .edit(|document| document.insert_text(&text, &at))
+                .is_none()
+            {
+                return;
+            }
+
+            let y = y.min(self.document().len().saturating_sub(1));
+            self.cursor_position = Position::new(vi::first_non_blank(self.document(), y), y);
+        } else {
+            let len = self.document().row(position.y).map_or(0, Row::len);
+            let at = if before {
+                position
+            } else {
+                Position::new((position.x + 1).min(len), position.y)
+            };
+            if let Some(end) = self.edit(|document| document.insert_text(&text, &at)) {
+                self.cursor_position = Position::new(end.x.saturating_sub(1), end.y);
+            }
+        }
+    }
+
+    fn selection(&self) -> Option<(Position, Position)> {
+        let anchor = self.anchor?;
+        let cursor = self.cursor_position;
+        let (start, mut end) = if (cursor.y, cursor.x) < (anchor.y, anchor.x) {
+            (cursor, anchor)
+        } else {
+            (anchor, cursor)
+        };
+
+        if self.mode == Mode::Visual {
+            end.x += 1;
+        }
+
+        Some((start, end))
+    }
+
+    fn execute(&mut self, command: Command) -> crossterm::Result<()> {
+        match command {
+            Command::Quit => {
+                if self.buffers.iter().any(|buffer| buffer.document.is_dirty()) {
+                    match self.prompt("Type 'y' to quit without saving: ", |_, _, _| Ok(()))? {
+                        Some(response) if response.to_lowercase().as_str() == "y" => {
+                            self.running = false
+                        }
+                        _ => {
+                            self.status_message =
+                                StatusMessage::from("[WARNING] File not saved".to_string())
+                        }
+                    }
+                } else {
+                    self.running = false;
+                }
+            }
+            Command::Save => self.save()?,
+            Command::Find => self.search(false)?,
+            Command::RegexFind => self.search(true)?,
+            Command::Replace => self.replace()?,
+            Command::Open => self.open_prompt()?,
+            Command::PickBuffer => self.pick_buffer()?,
+            Command::NextBuffer => self.switch_buffer((self.current + 1) % self.buffers.len()),
+            Command::PreviousBuffer => {
+                self.switch_buffer((self.current + self.buffers.len() - 1) % self.buffers.len())
+            }
+            Command::ConvertLineEndings => {
+                let ending =
+                    self.prompt("Convert line endings to (lf/crlf): ", |_, _, _| Ok(()))?;
+
+                match ending.map(|ending| ending.to_lowercase()).as_deref() {
+                    Some("lf") => {
+                        self.edit(|document| document.set_line_ending(LineEnding::Lf));
+                    }
+                    Some("crlf") => {
+                        self.edit(|document| document.set_line_ending(LineEnding::Crlf));
+                    }
+                    Some(_) => {
+                        self.status_message =
+                            StatusMessage::from("[WARNING] Unknown line ending style".to_string())
+                    }
+                    None => {}
+                }
+            }
+            Command::Undo => {
+                if let Some(position) = self.document_mut().undo() {
+                    self.cursor_position = position;
+                } else {
+                    self.status_message =
+                        StatusMessage::from("[WARNING] Nothing to undo".to_string());
+                }
+            }
+            Command::Redo => {
+                if let Some(position) = self.document_mut().redo() {
+                    self.cursor_position = position;
+                } else {
+                    self.status_message =
+                        StatusMessage::from("[WARNING] Nothing to redo".to_string());
+                }
+            }
+            Command::MoveUp | Command::MoveDown | Command::MoveLeft | Command::MoveRight => {
+                self.deselect();
+                self.move_cursor(match command {
+                    Command::MoveUp => KeyCode::Up,
+                    Command::MoveDown => KeyCode::Down,
+                    Command::MoveLeft => KeyCode::Left,
+                    _ => KeyCode::Right,
+                })?;
+            }
+            Command::SelectUp => self.select(KeyCode::Up)?,
+            Command::SelectDown => self.select(KeyCode::Down)?,
+            Command::SelectLeft => self.select(KeyCode::Left)?,
+            Command::SelectRight => self.select(KeyCode::Right)?,
+            Command::Cut | Command::Copy => match self.selection() {
+                Some((start, end)) => {
+                    let text = self.document().text(&start, &end);
+                    self.set_register(text, false)?;
+
+                    if command == Command::Cut {
+                        self.delete_selection();
+                    }
+                }
+                None => {
+                    self.status_message =
+                        StatusMessage::from("[WARNING] Nothing selected".to_string())
+                }
+            },
+            Command::Paste => {
+                self.delete_selection();
+
+                let text = self.register.text.clone();
+                let at = if self.register.linewise {
+                    Position::new(0, self.cursor_position.y)
+                } else {
+                    self.cursor_position
+                };
+
+                if let Some(end) = self.edit(|document| document.insert_text(&text, &at)) {
+                    self.cursor_position = end;
+                }
+            }
+            Command::InsertNewline => {
+                self.delete_selection();
+                self.insert_newline();
+            }
+            Command::InsertTab => {
+                self.delete_selection();
+
+                let text = match self.indent() {
+                    Indent::Tabs => "\t".to_string(),
+                    Indent::Spaces(width) => " ".repeat(width - self.cursor_column() % width),
+                };
+                let position = self.cursor_position;
+
+                if let Some(end) = self.edit(|document| document.insert_text(&text, &position)) {
+                    self.cursor_position = end;
+                }
+            }
+            Command::Delete => {
+                if !self.delete_selection() {
+                    let position = self.cursor_position;
+                    self.edit(|document| document.delete(&position));
+                }
+            }
+            Command::Backspace => {
+                if !self.delete_selection()
+                    && !self.delete_indent()
+                    && (self.cursor_position.x > 0 || self.cursor_position.y > 0)
+                {
+                    let cursor = self.cursor_position;
+                    self.move_cursor(KeyCode::Left)?;
+                    let position = self.cursor_position;
+
+                    if self.edit(|document| document.delete(&position)).is_none() {
+                        self.cursor_position = cursor;
+                    }
+                }
+            }
+            Command::IndentLines | Command::DedentLines => {
+                let (start, end) = self.selected_lines();
+                let row_len =
+                    |editor: &Self, y: usize| editor.document().row(y).map_or(0, Row::len);
+                let cursor = self.cursor_position;
+                let anchor = self.anchor;
+                let before = (row_len(self, cursor.y), anchor.map(|a| row_len(self, a.y)));
+
+                let edited = if command == Command::IndentLines {
+                    let unit = self.indent().unit();
+                    self.edit(|document| document.indent_lines(start, end, &unit))
+                } else {
+                    let width = match self.indent() {
+                        Indent::Tabs => self.config.tab_size,
+                        Indent::Spaces(width) => width,
+                    };
+                    self.edit(|document| document.dedent_lines(start, end, width))
+                };
+
+                if edited.is_none() {
+                    return Ok(());
+                }
+
+                let shift =
+                    |x: usize, before: usize, after: usize| (x + after).saturating_sub(before);
+
+                self.cursor_position.x = shift(cursor.x, before.0, row_len(self, cursor.y));
+                self.anchor = anchor.zip(before.1).map(|(anchor, len)| {
+                    Position::new(shift(anchor.x, len, row_len(self, anchor.y)), anchor.y)
+                });
+            }
+            Command::SplitHorizontal => self.split(Split::Horizontal),
+            Command::SplitVertical => self.split(Split::Vertical),
+            Command::ClosePane => self.close_pane(),
+            Command::NextPane => {
+                let panes = self.arrange()?.panes;
+                let position = panes
+                    .iter()
+                    .position(|(index, _)| *index == self.focus)
+                    .unwrap_or(0);
+                self.focus_pane(panes[(position + 1) % panes.len()].0);
+            }
+            Command::FocusUp => self.focus_direction(KeyCode::Up)?,
+            Command::FocusDown => self.focus_direction(KeyCode::Down)?,
+            Command::FocusLeft => self.focus_direction(KeyCode::Left)?,
+            Command::FocusRight => self.focus_direction(KeyCode::Right)?,
+            Command::GrowHeight => self.resize_pane(Split::Horizontal, RESIZE_STEP),
+            Command::ShrinkHeight => self.resize_pane(Split::Horizontal, -RESIZE_STEP),
+            Command::GrowWidth => self.resize_pane(Split::Vertical, RESIZE_STEP),
+            Command::ShrinkWidth => self.resize_pane(Split::Vertical, -RESIZE_STEP),
+            Command::CommandPalette => self.command_palette()?,
+            Command::GotoLine => self.goto_line()?,
+            Command::MatchBracket => match self.bracket_pair() {
+                Some((_, partner)) => self.cursor_position = partner,
+                None => {
+                    self.status_message =
+                        StatusMessage::from("[WARNING] No matching bracket".to_string())
+                }
+            },
+            Command::ToggleSoftWrap => {
+                self.config.soft_wrap = !self.config.soft_wrap;
+                self.sync_pane();
+
+                for pane in &mut self.panes {
+                    pane.offset.x = 0;
+                }
+
+                self.offset.x = 0;
+                self.status_message = StatusMessage::from(format!(
+                    "[INFO] Soft wrap {}",
+                    if self.config.soft_wrap {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    }
+                ));
+            }
+            Command::CycleLineNumbers => {
+                self.config.line_numbers = self.config.line_numbers.next();
+                self.status_message = StatusMessage::from(format!(
+                    "[INFO] Line numbers: {}",
+                    self.config.line_numbers
+                ));
+            }
+            Command::ToggleBackupOnSave => {
+                self.config.backup_on_save = !self.config.backup_on_save;
+                self.status_message = StatusMessage::from(format!(
+                    "[INFO] Backup on save {}",
+                    if self.config.backup_on_save {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    }
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn insert_newline(&mut self) {
+        let position = self.cursor_position;
+        let mut text = "\n".to_string();
+        let mut cursor = None;
+
+        if let Some(row) = self
+            .document()
+            .row(position.y)
+            .filter(|_| self.config.auto_indent)
+        {
+            let graphemes = row.graphemes();
+            let x = position.x.min(graphemes.len());
+            let leading = self.leading_whitespace(position.y, x);
+            let is_blank = |grapheme: &&&str| grapheme.chars().all(char::is_whitespace);
+            let before = graphemes[..x]
+                .iter()
+                .rev()
+                .find(|grapheme| !is_blank(grapheme));
+            let after = graphemes[x..].iter().find(|grapheme| !is_blank(grapheme));
+
+            text.push_str(&leading);
+
+            if let Some(opener) = before
+                .and_then(|grapheme| grapheme.chars().next())
+                .filter(|ch| self.indent_openers().contains(*ch))
+            {
+                text.push_str(&self.indent().unit());
+
+                let closer = match opener {
+                    '{' => Some("}"),
+                    '[' => Some("]"),
+                    '(' => Some(")"),
+                    _ => None,
+                };
+
+                if closer.is_some() && after.copied() == closer {
+                    cursor = Some(Position::new(text.chars().count() - 1, position.y + 1));
+                    text.push('\n');
+                    text.push_str(&leading);
+                }
+            }
+        }
+
+        if let Some(end) = self.edit(|document| document.insert_text(&text, &position)) {
+            self.cursor_position = cursor.unwrap_or(end);
+        }
+    }
+
+    fn delete_indent(&mut self) -> bool {
+        let position = self.cursor_position;
+        let width = match self.indent() {
+            Indent::Spaces(width) if position.x > 0 => width,
+            _ => return false,
+        };
+
+        if self.leading_whitespace(position.y, position.x) != " ".repeat(position.x) {
+            return false;
+        }
+
+        let count = (position.x - 1) % width + 1;
+        let start = Position::new(position.x - count, position.y);
+
+        if self
+            .edit(|document| document.delete_range(&start, &position))
+            .is_some()
+        {
+            self.cursor_position = start;
+        }
+
+        true
+    }
+
+    fn leading_whitespace(&self, y: usize, limit: usize) -> String {
+        self.document().row(y).map_or_else(String::new, |row| {
+            row.graphemes()
+                .into_iter()
+                .take(limit)
+                .take_while(|grapheme| grapheme.chars().all(char::is_whitespace))
+                .collect()
+        })
+    }
+
+    fn indent_openers(&self) -> &str {
+        let file_type = self.document().file_type();
+
+        self.config
+            .indent_openers
+            .get(&file_type.name().to_lowercase())
+            .map_or(file_type.indent_openers(), String::as_str)
+    }
+
+    fn bracket_pair(&self) -> Option<(Position, Position)> {
+        let position = self.cursor_position;
+        let before = position
+            .x
+            .checked_sub(1)
+            .map(|x| Position::new(x, position.y));
+
+        [Some(position), before]
+            .into_iter()
+            .flatten()
+            .find_map(|bracket| Some((bracket, self.document().matching_bracket(&bracket)?)))
+    }
+
+    fn selected_lines(&self) -> (usize, usize) {
+        match self.selection() {
+            Some((start, end)) if end.y > start.y && end.x == 0 && self.mode != Mode::Visual => {
+                (start.y, end.y - 1)
+            }
+            Some((start, end)) => (start.y, end.y),
+            None => (self.cursor_position.y, self.cursor_position.y),
+        }
+    }
+
+    fn indent(&self) -> Indent {
+        self.document()
+            .indent()
+            .unwrap_or(Indent::Spaces(self.config.tab_size))
+    }
+
+    fn document(&self) -> &Document {
+        &self.buffers[self.current].document
+    }
+
+    fn document_mut(&mut self) -> &mut Document {
+        &mut self.buffers[self.current].document
+    }
+
+    fn edit<T>(
+        &mut self,
+        edit: impl FnOnce(&mut Document) -> Result<T, DocumentError>,
+    ) -> Option<T> {
+        match edit(self.document_mut()) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.status_message = StatusMessage::from(format!("[WARNING] {error}"));
+                None
+            }
+        }
+    }
+
+    fn open(&mut self, filename: &str) -> bool {
+        if let Some(index) = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.document.filename.as_deref() == Some(filename))
+        {
+            self.switch_buffer(index);
+            return true;
+        }
+
+        match Document::open(filename) {
+            Ok(mut document) => {
+                document.set_tab_size(self.config.tab_size);
+
+                if document.is_read_only() {
+                    self.status_message = StatusMessage::from(format!(
+                        "[WARNING] {filename} is binary or not valid UTF-8, opened read-only"
+                    ));
+                }
+
+                self.buffers.push(Buffer::from(document));
+                self.switch_buffer(self.buffers.len() - 1);
+                true
+            }
+            Err(error) => {
+                self.status_message = StatusMessage::from(format!("[ERROR] {error}"));
+                false
+            }
+        }
+    }
+
+    fn switch_buffer(&mut self, index: usize) {
+        if let Some(buffer) = self.buffers.get_mut(self.current) {
+            buffer.cursor_position = self.cursor_position;
+            buffer.offset = self.offset;
+        }
+
+        self.current = index;
+        self.deselect_all();
+        self.cursor_position = self.buffers[index].cursor_position;
+        self.offset = self.buffers[index].offset;
+        self.clamp_cursor();
+    }
+
+    fn clamp_cursor(&mut self) {
+        self.cursor_position = self.document().clamp(&self.cursor_position);
+    }
+
+    fn sync_pane(&mut self) {
+        self.panes[self.focus] = Pane {
+            buffer: self.current,
+            cursor_position: self.cursor_position,
+            offset: self.offset,
+        };
+    }
+
+    fn load_pane(&mut self, index: usize) {
+        let pane = self.panes[index];
+
+        self.focus = index;
+        self.current = pane.buffer;
+        self.deselect_all();
+        self.cursor_position = pane.cursor_position;
+        self.offset = pane.offset;
+        self.clamp_cursor();
+    }
+
+    fn focus_pane(&mut self, index: usize) {
+        self.sync_pane();
+        self.load_pane(index);
+    }
+
+    fn sync_panes(&mut self) {
+        for (buffer, Buffer { document, .. }) in self.buffers.iter_mut().enumerate() {
+            let changes = document.take_changes();
+
+            if changes.is_empty() {
+                continue;
+            }
+
+            for (index, pane) in self.panes.iter_mut().enumerate() {
+                if index == self.focus || pane.buffer != buffer {
+                    continue;
+                }
+
+                for change in &changes {
+                    pane.cursor_position = change.shift(&pane.cursor_position);
+                    pane.offset.y = change.shift_line(pane.offset.y);
+                }
+
+                pane.cursor_position = document.clamp(&pane.cursor_position);
+            }
+        }
+    }
+
+    fn command_palette(&mut self) -> crossterm::Result<()> {
+        let mut matches = self.rank_commands("");
+        self.selected_candidate = 0;
+        self.candidates = self.format_candidates(&matches);
+
+        let query = self.prompt("Command: ", |editor, key, query| {
+            match key.code {
+                KeyCode::Up => {
+                    editor.selected_candidate = editor.selected_candidate.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    if editor.selected_candidate + 1 < matches.len() {
+                        editor.selected_candidate += 1;
+                    }
+                }
+                _ => {
+                    matches = editor.rank_commands(query);
+                    editor.selected_candidate = 0;
+                    editor.candidates = editor.format_candidates(&matches);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        let selected = self.selected_candidate;
+        self.candidates.clear();
+        self.selected_candidate = 0;
+
+        if query.is_some() {
+            if let Some(command) = matches.get(selected) {
+                self.execute(*command)?;
+            } else {
+                self.status_message =
+                    StatusMessage::from("[WARNING] No command matches".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rank_commands(&self, query: &str) -> Vec<Command> {
+        let mut matches: Vec<(usize, Command)> = Command::all()
+            .filter(|command| *command != Command::CommandPalette)
+            .filter_map(|command| Some((fuzzy::score(query, command.name())?, command)))
+            .collect();
+
+        matches.sort_by_key(|(score, command)| (Reverse(*score), command.name().len()));
+        matches.truncate(PALETTE_HEIGHT);
+
+        matches.into_iter().map(|(_, command)| command).collect()
+    }
+
+    fn format_candidates(&self, commands: &[Command]) -> Vec<String> {
+        commands
+            .iter()
+            .map(|command| {
+                let keys = self
+                    .config
+                    .keymap
+                    .keys(*command)
+                    .map(keymap::format_keys)
+                    .unwrap_or_default();
+
+                format!("{:<24}{keys:<16}{}", command.name(), command.description())
+            })
+            .collect()
+    }
+
+    fn goto_line(&mut self) -> crossterm::Result<()> {
+        let line = match self.prompt("Go to line: ", |_, _, _| Ok(()))? {
+            Some(line) if !line.is_empty() => line,
+            _ => return Ok(()),
+        };
+
+        match line.trim().parse::<usize>() {
+            Ok(number) if number >= 1 => {
+                self.cursor_position = Position::new(0, number - 1);
+                self.clamp_cursor();
+            }
+            _ => {
+                self.status_message =
+                    StatusMessage::from(format!("[WARNING] Invalid line number '{line}'"))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn focus_direction(&mut self, direction: KeyCode) -> crossterm::Result<()> {
+        let panes = self.arrange()?.panes;
+
+        if let Some(index) = neighbour(&panes, self.focus, direction) {
+            self.focus_pane(index);
+        }
+
+        Ok(())
+    }
+
+    fn split(&mut self, split: Split) {
+        self.sync_pane();
+
+        let index = self.panes.len();
+        self.panes.push(self.panes[self.focus]);
+        self.layout.split(self.focus, index, split);
+        self.load_pane(index);
+    }
+
+    fn close_pane(&mut self) {
+        if self.panes.len() == 1 {
+            self.status_message =
+                StatusMessage::from("[WARNING] Cannot close the last pane".to_string());
+            return;
+        }
+
+        self.buffers[self.current].cursor_position = self.cursor_position;
+        self.buffers[self.current].offset = self.offset;
+
+        self.layout.remove(self.focus);
+        self.panes.remove(self.focus);
+        self.load_pane(self.layout.first_pane());
+    }
+
+    fn resize_pane(&mut self, split: Split, delta: i16) {
+        if !self.layout.resize(self.focus, split, delta) {
+            self.status_message =
+                StatusMessage::from("[WARNING] No split to resize in that direction".to_string());
+        }
+    }
+
+    fn resize(&mut self) -> crossterm::Result<()> {
+        self.sync_pane();
+
+        for (index, rect) in self.arrange()?.panes {
+            let area = self.text_area(index, &rect);
+            let pane = &mut self.panes[index];
+            let document = &self.buffers[pane.buffer].document;
+
+            pane.cursor_position = document.clamp(&pane.cursor_position);
+
+            if self.config.soft_wrap {
+                wrap::follow(
+                    document,
+                    &mut pane.offset,
+                    &pane.cursor_position,
+                    area.width,
+                    area.height,
+                );
+                continue;
+            }
+
+            let column = document.row(pane.cursor_position.y).map_or(0, |row| {
+                row.column(pane.cursor_position.x, document.tab_size())
+            });
+
+            follow(
+                &mut pane.offset,
+                pane.cursor_position.y,
+                column,
+                area.width,
+                area.height,
+            );
+        }
+
+        let pane = self.panes[self.focus];
+        self.cursor_position = pane.cursor_position;
+        self.offset = pane.offset;
+
+        Terminal::clear_all()
+    }
+
+    fn arrange(&self) -> crossterm::Result<Arrangement> {
+        let size = Terminal::size()?;
+        let area = Rect::new(0, 0, size.width as usize, size.height as usize + 1);
+        let mut arrangement = Arrangement::default();
+
+        self.layout.arrange(area, &mut arrangement);
 
-        let document = if args.len() > 1 {
-            let filename = &args[1];
-            Document::open(filename)
+        Ok(arrangement)
+    }
+
+    fn viewport(&self) -> crossterm::Result<Rect> {
+        let rect = self.arrange()?.rect(self.focus).unwrap_or_default();
+
+        Ok(self.text_area(self.focus, &rect))
+    }
+
+    fn text_area(&self, index: usize, rect: &Rect) -> Rect {
+        let buffer = if index == self.focus {
+            self.current
         } else {
-            Document::default()
+            self.panes[index].buffer
         };
 
-        Editor {
-            running: true,
-            cursor_position: Position::default(),
-            document,
-            offset: Position::default(),
-            status_message: StatusMessage::from(
-                "[HELP] CTRL-Q = quit | CTRL-S = save | CTRL-F = find".to_string(),
-            ),
+        let gutter = match self.config.line_numbers {
+            LineNumbers::Off => 0,
+            _ => self.buffers[buffer].document.len().max(1).to_string().len() + 1,
         }
-    }
-}
+        .min(rect.width);
 
-impl Editor {
-    pub fn run(&mut self) -> crossterm::Result<()> {
-        Terminal::enter_alternate_screen()?;
-        Terminal::enable_raw_mode()?;
+        Rect::new(
+            rect.x + gutter,
+            rect.y,
+            rect.width - gutter,
+            rect.height.saturating_sub(1),
+        )
+    }
 
-        while self.running {
-            self.refresh_screen()?;
-            self.process_event()?;
+    fn open_prompt(&mut self) -> crossterm::Result<()> {
+        if let Some(filename) = self.prompt("Open file: ", |_, _, _| Ok(()))? {
+            if !filename.is_empty() {
+                self.open(&filename);
+            }
         }
 
-        Terminal::disable_raw_mode()?;
-        Terminal::leave_alternate_screen()
+        Ok(())
     }
 
-    fn refresh_screen(&self) -> crossterm::Result<()> {
-        Terminal::cursor_hide()?;
-        Terminal::cursor_position(&Position::default())?;
+    fn pick_buffer(&mut self) -> crossterm::Result<()> {
+        let list = self
+            .buffers
+            .iter()
+            .enumerate()
+            .map(|(i, buffer)| {
+                let name = buffer.document.filename.as_deref().unwrap_or("[No Name]");
+                let dirty = if buffer.document.is_dirty() { "+" } else { "" };
+                format!("{}:{name}{dirty}", i + 1)
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let choice = match self.prompt(&format!("{list} | Buffer: "), |_, _, _| Ok(()))? {
+            Some(choice) if !choice.is_empty() => choice,
+            _ => return Ok(()),
+        };
+
+        let index = match choice.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= self.buffers.len() => Some(number - 1),
+            Ok(_) => None,
+            Err(_) => {
+                let matches: Vec<usize> = self
+                    .buffers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, buffer)| {
+                        buffer
+                            .document
+                            .filename
+                            .as_ref()
+                            .is_some_and(|filename| filename.contains(&choice))
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
 
-        self.draw_rows()?;
-        self.draw_status_bar()?;
-        self.draw_status_message()?;
+                if matches.len() == 1 {
+                    Some(matches[0])
+                } else {
+                    None
+                }
+            }
+        };
 
-        Terminal::cursor_position(&Position::new(
-            self.cursor_position.x.saturating_sub(self.offset.x),
-            self.cursor_position.y.saturating_sub(self.offset.y),
-        ))?;
-        Terminal::cursor_show()?;
+        if let Some(index) = index {
+            self.switch_buffer(index);
+        } else {
+            self.status_message =
+                StatusMessage::from(format!("[WARNING] No buffer matches '{choice}'"));
+        }
 
-        Terminal::flush()
+        Ok(())
     }
 
-    fn process_event(&mut self) -> crossterm::Result<()> {
-        if let Event::Key(key) = Terminal::read_event()? {
-            match key {
-                KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::CONTROL,
-                } => {
-                    if self.document.is_dirty() {
-                        match self.prompt("Type 'y' to quit without saving: ")? {
-                            Some(response) if response.to_lowercase().as_str() == "y" => {
-                                self.running = false
-                            }
-                            _ => {
-                                self.status_message =
-                                    StatusMessage::from("[WARNING] File not saved".to_string())
-                            }
-                        }
-                    } else {
-                        self.running = false;
-                    }
+    fn save(&mut self) -> crossterm::Result<()> {
+        if self.document().filename.is_none() {
+            match self.prompt("Save as: ", |_, _, _| Ok(()))? {
+                Some(filename) if !filename.is_empty() => {
+                    self.document_mut().set_filename(filename)
                 }
-                KeyEvent {
-                    code: KeyCode::Char('s'),
-                    modifiers: KeyModifiers::CONTROL,
-                } => {
-                    if self.document.filename.is_some() {
-                        self.document.save()?;
-                    } else {
-                        let filename = self.prompt("Save as: ")?;
+                _ => {
+                    self.status_message =
+                        StatusMessage::from("[WARNING] File not saved".to_string());
+                    return Ok(());
+                }
+            }
+        }
 
-                        if filename.is_some() {
-                            self.document.filename = filename;
-                            self.document.save()?;
-                        } else {
-                            self.status_message =
-                                StatusMessage::from("[WARNING] File not saved".to_string());
+        let backup = self.config.backup_on_save;
+
+        self.status_message = match self.document_mut().save(backup) {
+            Ok(()) => StatusMessage::from("[INFO] File saved".to_string()),
+            Err(error) => StatusMessage::from(format!("[ERROR] {error}")),
+        };
+
+        Ok(())
+    }
+
+    fn search(&mut self, regex: bool) -> crossterm::Result<()> {
+        let old_position = self.cursor_position;
+        let old_offset = self.offset;
+        let mut found = false;
+
+        let prompt = if regex {
+            "Regex search (ESC to cancel, arrows to navigate): "
+        } else {
+            "Search (ESC to cancel, arrows to navigate): "
+        };
+
+        let query = self.prompt(prompt, |editor, key, query| {
+            let (at, direction) = match key.code {
+                KeyCode::Right | KeyCode::Down => (
+                    Position::new(editor.cursor_position.x + 1, editor.cursor_position.y),
+                    SearchDirection::Forward,
+                ),
+                KeyCode::Left | KeyCode::Up => (editor.cursor_position, SearchDirection::Backward),
+                _ => (old_position, SearchDirection::Forward),
+            };
+
+            editor.search_query = if query.is_empty() {
+                None
+            } else if regex {
+                Regex::new(query).ok()
+            } else {
+                Regex::new(&regex::escape(query)).ok()
+            };
+
+            let position = editor
+                .search_query
+                .as_ref()
+                .and_then(|pattern| editor.document().find(pattern, &at, direction));
+            found = position.is_some();
+
+            if let Some(position) = position {
+                editor.cursor_position = position;
+                editor.scroll()?;
+            } else if query.is_empty() {
+                editor.cursor_position = old_position;
+                editor.offset = old_offset;
+            }
+
+            Ok(())
+        })?;
+
+        self.search_query = None;
+
+        if query.is_none() {
+            self.cursor_position = old_position;
+            self.offset = old_offset;
+        } else if !found {
+            self.status_message =
+                StatusMessage::from("[WARNING] Search query not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn replace(&mut self) -> crossterm::Result<()> {
+        let pattern = match self.prompt("Replace (regex): ", |_, _, _| Ok(()))? {
+            Some(pattern) if !pattern.is_empty() => pattern,
+            _ => return Ok(()),
+        };
+
+        let pattern = match Regex::new(&pattern) {
+            Ok(pattern) => pattern,
+            Err(error) => {
+                self.status_message = StatusMessage::from(format!("[WARNING] {error}"));
+                return Ok(());
+            }
+        };
+
+        let replacement = match self.prompt("Replace with: ", |_, _, _| Ok(()))? {
+            Some(replacement) => expand_backreferences(&replacement),
+            None => return Ok(()),
+        };
+
+        let mode = self.prompt("Replace (o)ne, (a)ll or (i)nteractive: ", |_, _, _| Ok(()))?;
+
+        let count = match mode.as_deref() {
+            Some("o") => self.replace_next(&pattern, &replacement),
+            Some("a") => self.edit(|document| {
+                document.replace_all(&pattern, &replacement, &Position::default())
+            }),
+            Some("i") => self.replace_interactive(&pattern, &replacement)?,
+            _ => return Ok(()),
+        };
+
+        if let Some(count) = count {
+            self.status_message =
+                StatusMessage::from(format!("[INFO] {count} substitution(s) made"));
+        }
+
+        Ok(())
+    }
+
+    fn replace_next(&mut self, pattern: &Regex, replacement: &str) -> Option<usize> {
+        let Some(position) =
+            self.document()
+                .find(pattern, &self.cursor_position, SearchDirection::Forward)
+        else {
+            return Some(0);
+        };
+
+        match self.edit(|document| document.replace(pattern, replacement, &position))? {
+            Some(end) => {
+                self.cursor_position = end;
+                Some(1)
+            }
+            None => Some(0),
+        }
+    }
+
+    fn replace_interactive(
+        &mut self,
+        pattern: &Regex,
+        replacement: &str,
+    ) -> crossterm::Result<Option<usize>> {
+        let mut at = self.cursor_position;
+        let mut count = 0;
+        let mut failed = false;
+
+        self.search_query = Some(pattern.clone());
+
+        while let Some(position) = self
+            .document()
+            .find(pattern, &at, SearchDirection::Forward)
+            .filter(|position| (position.y, position.x) >= (at.y, at.x))
+        {
+            self.cursor_position = position;
+            self.scroll()?;
+
+            match self
+                .prompt(
+                    "Replace this match? (y)es, (n)o, (a)ll, (q)uit: ",
+                    |_, _, _| Ok(()),
+                )?
+                .as_deref()
+            {
+                Some("y") => {
+                    let end = match self
+                        .edit(|document| document.replace(pattern, replacement, &position))
+                    {
+                        Some(Some(end)) => {
+                            count += 1;
+                            end
                         }
-                    }
-                }
-                KeyEvent {
-                    code: KeyCode::Char('f'),
-                    modifiers: KeyModifiers::CONTROL,
-                } => {
-                    if let Some(query) = self.prompt("Search: ")? {
-                        if let Some(position) = self.document.find(&query) {
-                            self.cursor_position = position;
-                            self.scroll()?;
-                        } else {
-                            self.status_message =
-                                StatusMessage::from("[WARNING] Search query not found".to_string());
+                        Some(None) => self.match_end(pattern, &position),
+                        None => {
+                            failed = true;
+                            break;
                         }
-                    }
-                }
-                KeyEvent {
-                    code: direction @ (KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right),
-                    modifiers: KeyModifiers::NONE,
-                } => {
-                    self.move_cursor(direction)?;
-                }
-                KeyEvent {
-                    code: KeyCode::Char(ch),
-                    modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
-                } => {
-                    self.document.insert(ch, &self.cursor_position);
-                    self.move_cursor(KeyCode::Right)?;
-                }
-                KeyEvent {
-                    code: KeyCode::Enter,
-                    modifiers: KeyModifiers::NONE,
-                } => {
-                    self.document.insert_newline(&self.cursor_position);
-                    self.move_cursor(KeyCode::Down)?;
-                    self.cursor_position.x = 0;
-                }
-                KeyEvent {
-                    code: KeyCode::Tab,
-                    modifiers: KeyModifiers::NONE,
-                } => {
-                    for _ in 0..TAB_SIZE {
-                        self.document.insert(' ', &self.cursor_position);
-                        self.move_cursor(KeyCode::Right)?;
-                    }
-                }
-                KeyEvent {
-                    code: KeyCode::Delete,
-                    modifiers: KeyModifiers::NONE,
-                } => {
-                    self.document.delete(&self.cursor_position);
+                    };
+
+                    at = if end == position {
+                        Position::new(end.x + 1, end.y)
+                    } else {
+                        end
+                    };
                 }
-                KeyEvent {
-                    code: KeyCode::Backspace,
-                    modifiers: KeyModifiers::NONE,
-                } => {
-                    if self.cursor_position.x > 0 || self.cursor_position.y > 0 {
-                        self.move_cursor(KeyCode::Left)?;
-                        self.document.delete(&self.cursor_position);
+                Some("n") => at = self.match_end(pattern, &position),
+                Some("a") => {
+                    match self
+                        .edit(|document| document.replace_all(pattern, replacement, &position))
+                    {
+                        Some(replaced) => count += replaced,
+                        None => failed = true,
                     }
+                    break;
                 }
-                _ => {}
+                _ => break,
             }
         }
 
-        self.scroll()
+        self.search_query = None;
+
+        Ok((!failed).then_some(count))
     }
 
-    fn draw_rows(&self) -> crossterm::Result<()> {
-        let width = Terminal::size()?.width;
-        let height = Terminal::size()?.height;
+    fn match_end(&self, pattern: &Regex, position: &Position) -> Position {
+        let len = self
+            .document()
+            .row(position.y)
+            .and_then(|row| row.matched(pattern, position.x))
+            .map_or(0, |found| found.graphemes(true).count());
+
+        Position::new(position.x + cmp::max(len, 1), position.y)
+    }
+
+    fn draw_rows(&self, screen: &mut Screen, index: usize, rect: &Rect) {
+        let pane = &self.panes[index];
+        let document = &self.buffers[pane.buffer].document;
+        let area = self.text_area(index, rect);
+        let gutter = area.x - rect.x;
+        let soft_wrap = self.config.soft_wrap;
+        let tab_size = document.tab_size();
+        let brackets = if index == self.focus {
+            self.bracket_pair()
+                .map_or(Vec::new(), |(bracket, partner)| vec![bracket, partner])
+        } else {
+            Vec::new()
+        };
+        let mut line = (pane.offset.y, if soft_wrap { pane.offset.x } else { 0 });
 
-        Terminal::set_bg_color(BG_COLOR)?;
+        for i in 0..area.height {
+            let (y, segment) = line;
+            let mut used = 0;
 
-        for i in 0..height {
-            Terminal::clear_current_line()?;
+            line = if soft_wrap {
+                wrap::next(document, line, area.width).unwrap_or((y + 1, 0))
+            } else {
+                (y + 1, 0)
+            };
 
-            if let Some(row) = self.document.row(i + self.offset.y) {
-                let row = row.render(self.offset.x as usize, (self.offset.x + width) as usize);
+            if gutter > 0 && y < document.len() && segment == 0 {
+                let color = if y == pane.cursor_position.y {
+                    self.config.current_line_number_color
+                } else {
+                    self.config.line_number_color
+                };
+                let label = self.config.line_numbers.label(y, pane.cursor_position.y);
 
-                Terminal::set_fg_color(FG_COLOR)?;
-                println!("{row}\r");
+                screen.print(
+                    rect.x,
+                    rect.y + i,
+                    &format!("{label:>width$} ", width = gutter - 1),
+                    color,
+                    self.config.bg_color,
+                );
             } else {
-                Terminal::set_fg_color(TILDES_COLOR)?;
-                println!("~\r");
+                screen.fill(
+                    rect.x,
+                    rect.y + i,
+                    gutter,
+                    self.config.fg_color,
+                    self.config.bg_color,
+                );
             }
-        }
 
-        Ok(())
+            if let Some(row) = document.row(y) {
+                let mut marks = Vec::new();
+
+                if let Some((start, end)) = self.selection().filter(|_| index == self.focus) {
+                    if (start.y..=end.y).contains(&y) {
+                        let from = if y == start.y { start.x } else { 0 };
+                        let to = if y == end.y { end.x } else { row.len() };
+                        marks.push((from..to, highlighting::Type::Selection));
+                    }
+                }
+
+                marks.extend(
+                    brackets
+                        .iter()
+                        .filter(|bracket| bracket.y == y)
+                        .map(|bracket| (bracket.x..bracket.x + 1, highlighting::Type::Bracket)),
+                );
+
+                if let Some(query) = &self.search_query {
+                    marks.extend(
+                        row.find_all(query)
+                            .into_iter()
+                            .map(|range| (range, highlighting::Type::Match)),
+                    );
+                }
+
+                let (start, end) = if soft_wrap {
+                    let range = wrap::segment(document, y, segment, area.width);
+
+                    if segment > 0 {
+                        used = screen.print(
+                            area.x,
+                            area.y + i,
+                            wrap::INDICATOR,
+                            self.config.tildes_color,
+                            self.config.bg_color,
+                        );
+                    }
+
+                    (
+                        row.column(range.start, tab_size),
+                        row.column(range.end, tab_size),
+                    )
+                } else {
+                    (pane.offset.x, pane.offset.x + area.width)
+                };
+
+                let spans = row.render_spans(start, end, &marks, tab_size);
+
+                for (highlight, text) in spans {
+                    let (fg_color, bg_color) = match highlight {
+                        highlighting::Type::None => (self.config.fg_color, self.config.bg_color),
+                        highlighting::Type::Match => {
+                            (highlight.to_color(), self.config.match_bg_color)
+                        }
+                        highlighting::Type::Selection => {
+                            (self.config.fg_color, self.config.selection_bg_color)
+                        }
+                        highlighting::Type::Bracket => {
+                            (self.config.fg_color, self.config.bracket_bg_color)
+                        }
+                        highlight => (highlight.to_color(), self.config.bg_color),
+                    };
+
+                    used += screen.print(area.x + used, area.y + i, &text, fg_color, bg_color);
+                }
+            } else if area.width > 0 {
+                used = screen.print(
+                    area.x,
+                    area.y + i,
+                    "~",
+                    self.config.tildes_color,
+                    self.config.bg_color,
+                );
+            }
+
+            screen.fill(
+                area.x + used,
+                area.y + i,
+                area.width.saturating_sub(used),
+                self.config.fg_color,
+                self.config.bg_color,
+            );
+        }
     }
 
-    fn draw_status_bar(&self) -> crossterm::Result<()> {
-        if Terminal::size()?.height == 0 {
-            return Ok(());
+    fn draw_status_bar(&self, screen: &mut Screen, index: usize, rect: &Rect) {
+        if rect.height == 0 {
+            return;
         }
 
-        let width = Terminal::size()?.width as usize;
+        let pane = &self.panes[index];
+        let document = &self.buffers[pane.buffer].document;
+        let width = rect.width;
 
-        let filename = if let Some(filename) = &self.document.filename {
+        let filename = if let Some(filename) = &document.filename {
             filename
         } else {
             "[No Name]"
         };
 
-        let dirty = if self.document.is_dirty() {
+        let dirty = if document.is_read_only() {
+            " [RO] "
+        } else if document.is_dirty() {
             " [+] "
         } else {
             ""
         };
 
-        let position = format!("{}:{}", self.cursor_position.y, self.cursor_position.x);
+        let line_ending = match document.line_ending() {
+            Some(ending) => ending.to_string(),
+            None => "Mixed".to_string(),
+        };
 
-        let spaces =
-            if let Some(n) = width.checked_sub(filename.len() + dirty.len() + position.len()) {
-                " ".repeat(n)
-            } else {
-                String::new()
-            };
+        let indent = document
+            .indent()
+            .unwrap_or(Indent::Spaces(self.config.tab_size));
+
+        let position = format!(
+            "[{}/{}] {} | {} | {} | {}:{}",
+            pane.buffer + 1,
+            self.buffers.len(),
+            document.file_type().name(),
+            indent,
+            line_ending,
+            pane.cursor_position.y,
+            pane.cursor_position.x
+        );
+
+        let mode = if self.config.vi_mode && index == self.focus {
+            format!("[{}] ", self.mode)
+        } else {
+            String::new()
+        };
+
+        let spaces = if let Some(n) =
+            width.checked_sub(mode.len() + filename.width() + dirty.len() + position.len())
+        {
+            " ".repeat(n)
+        } else {
+            String::new()
+        };
+
+        let bar = Row::from(format!("{mode}{filename}{dirty}{spaces}{position}").as_str()).render(
+            0,
+            width,
+            self.config.tab_size,
+        );
+
+        let bg_color = if index == self.focus {
+            self.config.status_bar_bg_color
+        } else {
+            self.config.inactive_status_bar_bg_color
+        };
 
-        let mut bar = format!("{filename}{dirty}{spaces}{position}");
-        bar.truncate(width);
+        let y = rect.y + rect.height - 1;
+        let fg_color = self.config.status_bar_fg_color;
+        let used = screen.print(rect.x, y, &bar, fg_color, bg_color);
 
-        Terminal::set_bg_color(STATUS_BAR_BG_COLOR)?;
-        Terminal::set_fg_color(STATUS_BAR_FG_COLOR)?;
-        println!("{bar}\r");
-        Terminal::reset_color()
+        screen.fill(
+            rect.x + used,
+            y,
+            width.saturating_sub(used),
+            fg_color,
+            bg_color,
+        );
     }
 
-    fn draw_status_message(&self) -> crossterm::Result<()> {
-        Terminal::clear_current_line()?;
+    fn draw_separators(&self, screen: &mut Screen, separators: &[Rect]) {
+        for separator in separators {
+            for y in separator.y..separator.y + separator.height {
+                screen.print(
+                    separator.x,
+                    y,
+                    "│",
+                    self.config.tildes_color,
+                    self.config.bg_color,
+                );
+            }
+        }
+    }
+
+    fn draw_candidates(&self, screen: &mut Screen) {
+        let width = screen.width();
+        let bottom = screen.height().saturating_sub(2);
+        let count = self.candidates.len().min(bottom);
 
-        if Instant::now() - self.status_message.time < Duration::new(STATUS_MESSAGE_DURATION, 0) {
-            let mut message = self.status_message.text.clone();
-            message.truncate(Terminal::size()?.width as usize);
+        for (i, candidate) in self.candidates.iter().take(count).enumerate() {
+            let (fg_color, bg_color) = if i == self.selected_candidate {
+                (
+                    self.config.status_bar_fg_color,
+                    self.config.status_bar_bg_color,
+                )
+            } else {
+                (self.config.fg_color, self.config.bg_color)
+            };
 
-            print!("{message}");
+            let text = Row::from(candidate.as_str()).render(0, width, self.config.tab_size);
+            let y = bottom - count + i;
+            let used = screen.print(0, y, &text, fg_color, bg_color);
+
+            screen.fill(used, y, width.saturating_sub(used), fg_color, bg_color);
         }
+    }
 
-        Ok(())
+    fn draw_status_message(&self, screen: &mut Screen) {
+        if Instant::now() - self.status_message.time
+            < Duration::new(self.config.status_message_duration, 0)
+        {
+            let message = Row::from(self.status_message.text.as_str()).render(
+                0,
+                screen.width(),
+                self.config.tab_size,
+            );
+
+            screen.print(
+                0,
+                screen.height().saturating_sub(1),
+                &message,
+                self.config.fg_color,
+                self.config.bg_color,
+            );
+        }
     }
 
     fn move_cursor(&mut self, direction: KeyCode) -> crossterm::Result<()> {
-        let width = if let Some(row) = self.document.row(self.cursor_position.y) {
-            row.len() as u16
+        let width = if let Some(row) = self.document().row(self.cursor_position.y) {
+            row.len()
         } else {
             0
         };
-        let height = self.document.len() as u16;
+        let height = self.document().len();
 
         match direction {
-            KeyCode::Up => {
-                if self.cursor_position.y > 0 {
-                    self.cursor_position.y -= 1;
+            KeyCode::Up | KeyCode::Down if self.config.soft_wrap => {
+                let wrap_width = self.viewport()?.width;
+                let document = self.document();
+                let (segment, column) = wrap::locate(document, &self.cursor_position, wrap_width);
+                let line = (self.cursor_position.y, segment);
+                let line = if direction == KeyCode::Up {
+                    wrap::previous(document, line, wrap_width)
+                } else {
+                    wrap::next(document, line, wrap_width)
+                };
+
+                if let Some((y, segment)) = line {
+                    let x = wrap::index(document, y, segment, column, wrap_width);
+                    self.cursor_position = Position::new(x, y);
                 }
             }
-            KeyCode::Down => {
-                if self.cursor_position.y < height {
-                    self.cursor_position.y += 1;
-                }
+            KeyCode::Up if self.cursor_position.y > 0 => {
+                let column = self.cursor_column();
+                self.cursor_position.y -= 1;
+                self.cursor_position.x = self.column_to_x(column);
+            }
+            KeyCode::Down if self.cursor_position.y < height => {
+                let column = self.cursor_column();
+                self.cursor_position.y += 1;
+                self.cursor_position.x = self.column_to_x(column);
             }
             KeyCode::Left => {
                 if self.cursor_position.x > 0 {
@@ -280,7 +2219,7 @@ impl Editor {
                 } else if self.cursor_position.y > 0 {
                     self.cursor_position.y -= 1;
                     self.cursor_position.x =
-                        self.document.row(self.cursor_position.y).unwrap().len() as u16;
+                        self.document().row(self.cursor_position.y).unwrap().len();
                 }
             }
             KeyCode::Right => {
@@ -294,8 +2233,8 @@ impl Editor {
             _ => {}
         }
 
-        let width = if let Some(row) = self.document.row(self.cursor_position.y) {
-            row.len() as u16
+        let width = if let Some(row) = self.document().row(self.cursor_position.y) {
+            row.len()
         } else {
             0
         };
@@ -307,81 +2246,98 @@ impl Editor {
         Ok(())
     }
 
-    fn scroll(&mut self) -> crossterm::Result<()> {
-        let width = Terminal::size()?.width;
-        let height = Terminal::size()?.height;
+    fn cursor_column(&self) -> usize {
+        if let Some(row) = self.document().row(self.cursor_position.y) {
+            row.column(self.cursor_position.x, self.document().tab_size())
+        } else {
+            0
+        }
+    }
 
-        if self.cursor_position.x < self.offset.x {
-            self.offset.x = self.cursor_position.x;
-        } else if self.cursor_position.x >= self.offset.x.saturating_add(width) {
-            self.offset.x = self
-                .cursor_position
-                .x
-                .saturating_sub(width)
-                .saturating_add(1);
+    fn column_to_x(&self, column: usize) -> usize {
+        if let Some(row) = self.document().row(self.cursor_position.y) {
+            row.index(column, self.document().tab_size())
+        } else {
+            0
         }
+    }
 
-        if self.cursor_position.y < self.offset.y {
-            self.offset.y = self.cursor_position.y;
-        } else if self.cursor_position.y >= self.offset.y.saturating_add(height) {
-            self.offset.y = self
-                .cursor_position
-                .y
-                .saturating_sub(height)
-                .saturating_add(1);
+    fn scroll(&mut self) -> crossterm::Result<()> {
+        let viewport = self.viewport()?;
+
+        if self.config.soft_wrap {
+            let cursor = self.cursor_position;
+            let mut offset = self.offset;
+
+            wrap::follow(
+                self.document(),
+                &mut offset,
+                &cursor,
+                viewport.width,
+                viewport.height,
+            );
+            self.offset = offset;
+
+            return Ok(());
         }
 
+        let column = self.cursor_column();
+
+        follow(
+            &mut self.offset,
+            self.cursor_position.y,
+            column,
+            viewport.width,
+            viewport.height,
+        );
+
         Ok(())
     }
 
-    fn prompt(&mut self, prompt: &str) -> crossterm::Result<Option<String>> {
+    fn prompt<C>(&mut self, prompt: &str, mut callback: C) -> crossterm::Result<Option<String>>
+    where
+        C: FnMut(&mut Self, KeyEvent, &str) -> crossterm::Result<()>,
+    {
         let mut result = String::new();
 
-        let mut cursor_position = Position::new(prompt.len() as u16, Terminal::size()?.height + 1);
-        let mut offset = 0;
-
         loop {
-            let end = offset + Terminal::size()?.width as usize;
-            self.status_message = StatusMessage::from(format!(
-                "{}{}",
-                prompt,
-                &result[cmp::min(offset, result.len())..cmp::min(end, result.len())]
-            ));
-            self.refresh_screen()?;
+            let width = (Terminal::size()?.width as usize).saturating_sub(prompt.width() + 1);
+            let offset = result.width().saturating_sub(width);
+            let visible =
+                Row::from(result.as_str()).render(offset, offset + width, self.config.tab_size);
+
+            self.status_message = StatusMessage::from(format!("{prompt}{visible}"));
+            self.render(Some(Position::new(
+                prompt.width() + visible.width(),
+                Terminal::size()?.height as usize + 1,
+            )))?;
 
-            Terminal::cursor_position(&cursor_position)?;
+            let event = Terminal::read_event()?;
+
+            if let Event::Resize(..) = event {
+                self.resize()?;
+            }
 
-            if let Event::Key(key) = Terminal::read_event()? {
+            if let Event::Key(key) = event {
                 match key {
                     KeyEvent {
                         code: KeyCode::Char(ch),
                         modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                        ..
                     } => {
                         result.push(ch);
-
-                        if cursor_position.x < Terminal::size()?.width - 1 {
-                            cursor_position.x += 1;
-                        } else {
-                            offset += 1;
-                        }
                     }
                     KeyEvent {
                         code: KeyCode::Backspace,
                         modifiers: KeyModifiers::NONE,
+                        ..
                     } => {
-                        if !result.is_empty() {
-                            result.pop();
-
-                            if cursor_position.x > prompt.len() as u16 {
-                                cursor_position.x -= 1;
-                            } else {
-                                offset -= 1;
-                            }
-                        }
+                        result.pop();
                     }
                     KeyEvent {
                         code: KeyCode::Enter,
                         modifiers: KeyModifiers::NONE,
+                        ..
                     } => {
                         self.status_message = StatusMessage::default();
                         break;
@@ -389,14 +2345,90 @@ impl Editor {
                     KeyEvent {
                         code: KeyCode::Esc,
                         modifiers: KeyModifiers::NONE,
+                        ..
                     } => {
+                        self.status_message = StatusMessage::default();
                         return Ok(None);
                     }
                     _ => {}
                 }
+
+                callback(self, key, &result)?;
             }
         }
 
         Ok(Some(result))
     }
 }
+
+fn follow(offset: &mut Position, y: usize, column: usize, width: usize, height: usize) {
+    if column < offset.x {
+        offset.x = column;
+    } else if column >= offset.x.saturating_add(width) {
+        offset.x = column.saturating_sub(width).saturating_add(1);
+    }
+
+    if y < offset.y {
+        offset.y = y;
+    } else if y >= offset.y.saturating_add(height) {
+        offset.y = y.saturating_sub(height).saturating_add(1);
+    }
+}
+
+fn pane_at(arrangement: &Arrangement, column: u16, row: u16) -> Option<(usize, Rect)> {
+    let (column, row) = (column as usize, row as usize);
+
+    arrangement
+        .panes
+        .iter()
+        .find(|(_, rect)| {
+            (rect.x..rect.x + rect.width).contains(&column)
+                && (rect.y..rect.y + rect.height.saturating_sub(1)).contains(&row)
+        })
+        .copied()
+}
+
+fn neighbour(panes: &[(usize, Rect)], focus: usize, direction: KeyCode) -> Option<usize> {
+    let (_, from) = panes.iter().find(|(index, _)| *index == focus)?;
+
+    panes
+        .iter()
+        .filter(|(index, _)| *index != focus)
+        .filter_map(|(index, to)| {
+            let overlaps_x = to.x < from.x + from.width && from.x < to.x + to.width;
+            let overlaps_y = to.y < from.y + from.height && from.y < to.y + to.height;
+
+            let distance = match direction {
+                KeyCode::Up if overlaps_x && to.y + to.height <= from.y => from.y - to.y,
+                KeyCode::Down if overlaps_x && to.y >= from.y + from.height => to.y - from.y,
+                KeyCode::Left if overlaps_y && to.x + to.width <= from.x => from.x - to.x,
+                KeyCode::Right if overlaps_y && to.x >= from.x + from.width => to.x - from.x,
+                _ => return None,
+            };
+
+            Some((distance, *index))
+        })
+        .min()
+        .map(|(_, index)| index)
+}
+
+fn expand_backreferences(replacement: &str) -> String {
+    let mut result = String::new();
+    let mut chars = replacement.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('\\', Some(digit)) if digit.is_ascii_digit() => {
+                result.push_str(&format!("${{{digit}}}"));
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                result.push('\\');
+                chars.next();
+            }
+            _ => result.push(ch),
+        }
+    }
+
+    result
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+
+#[derive(Clone)]
+pub struct FileType {
+    name: String,
+    hl_opts: HighlightingOptions,
+    indent_openers: &'static str,
+}
+
+#[derive(Default, Clone)]
+pub struct HighlightingOptions {
+    pub numbers: bool,
+    pub quotes: &'static [char],
+    pub characters: bool,
+    pub comment: Option<&'static str>,
+    pub multiline_comment: Option<(&'static str, &'static str)>,
+    pub multiline_strings: &'static [(&'static str, &'static str, bool)],
+    pub heading: Option<&'static str>,
+    pub keywords: &'static [&'static str],
+    pub types: &'static [&'static str],
+    pub capitalized_types: bool,
+}
+
+impl Default for FileType {
+    fn default() -> FileType {
+        FileType {
+            name: String::from("No filetype"),
+            hl_opts: HighlightingOptions::default(),
+            indent_openers: "{[(",
+        }
+    }
+}
+
+impl From<&str> for FileType {
+    fn from(filename: &str) -> FileType {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+
+        match extension {
+            "rs" => FileType {
+                name: String::from("Rust"),
+                hl_opts: HighlightingOptions {
+                    numbers: true,
+                    characters: true,
+                    comment: Some("//"),
+                    multiline_comment: Some(("/*", "*/")),
+                    multiline_strings: &[("\"", "\"", true)],
+                    keywords: &[
+                        "as", "async", "await", "break", "const", "continue", "crate", "dyn",
+                        "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let",
+                        "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+                        "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+                        "use", "where", "while",
+                    ],
+                    types: &[
+                        "bool", "char", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16",
+                        "u32", "u64", "u128", "usize", "f32", "f64", "str",
+                    ],
+                    capitalized_types: true,
+                    ..HighlightingOptions::default()
+                },
+                indent_openers: "{[(",
+            },
+            "c" | "h" => FileType {
+                name: String::from("C"),
+                hl_opts: HighlightingOptions {
+                    numbers: true,
+                    quotes: &['"'],
+                    characters: true,
+                    comment: Some("//"),
+                    multiline_comment: Some(("/*", "*/")),
+                    keywords: &[
+                        "auto", "break", "case", "const", "continue", "default", "do", "else",
+                        "enum", "extern", "for", "goto", "if", "inline", "register", "restrict",
+                        "return", "sizeof", "static", "struct", "switch", "typedef", "union",
+                        "volatile", "while", "#include", "#define", "#ifdef", "#ifndef", "#endif",
+                        "NULL",
+                    ],
+                    types: &[
+                        "char", "double", "float", "int", "long", "short", "signed", "unsigned",
+                        "void", "size_t", "bool",
+                    ],
+                    ..HighlightingOptions::default()
+                },
+                indent_openers: "{[(",
+            },
+            "py" => FileType {
+                name: String::from("Python"),
+                hl_opts: HighlightingOptions {
+                    numbers: true,
+                    quotes: &['"', '\''],
+                    comment: Some("#"),
+                    multiline_strings: &[("\"\"\"", "\"\"\"", true), ("'''", "'''", true)],
+                    keywords: &[
+                        "and", "as", "assert", "async", "await", "break", "class", "continue",
+                        "def", "del", "elif", "else", "except", "False", "finally", "for", "from",
+                        "global", "if", "import", "in", "is", "lambda", "None", "nonlocal", "not",
+                        "or", "pass", "raise", "return", "True", "try", "while", "with", "yield",
+                    ],
+                    types: &[
+                        "bool", "bytes", "dict", "float", "int", "list", "object", "set", "str",
+                        "tuple",
+                    ],
+                    capitalized_types: true,
+                    ..HighlightingOptions::default()
+                },
+                indent_openers: ":{[(",
+            },
+            "md" | "markdown" => FileType {
+                name: String::from("Markdown"),
+                hl_opts: HighlightingOptions {
+                    quotes: &['`'],
+                    multiline_comment: Some(("<!--", "-->")),
+                    multiline_strings: &[("```", "```", false)],
+                    heading: Some("#"),
+                    ..HighlightingOptions::default()
+                },
+                indent_openers: "",
+            },
+            "toml" => FileType {
+                name: String::from("TOML"),
+                hl_opts: HighlightingOptions {
+                    numbers: true,
+                    quotes: &['"', '\''],
+                    comment: Some("#"),
+                    multiline_strings: &[("\"\"\"", "\"\"\"", true), ("'''", "'''", false)],
+                    heading: Some("["),
+                    keywords: &["true", "false"],
+                    ..HighlightingOptions::default()
+                },
+                indent_openers: "{[",
+            },
+            _ => FileType::default(),
+        }
+    }
+}
+
+impl FileType {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn highlighting_options(&self) -> &HighlightingOptions {
+        &self.hl_opts
+    }
+
+    pub fn indent_openers(&self) -> &'static str {
+        self.indent_openers
+    }
+}
@@ -0,0 +1,33 @@
+const MATCH_SCORE: usize = 1;
+const CONSECUTIVE_BONUS: usize = 5;
+const WORD_START_BONUS: usize = 10;
+
+pub fn score(pattern: &str, candidate: &str) -> Option<usize> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut start = 0;
+    let mut previous = None;
+
+    for ch in pattern.chars().filter(|ch| !ch.is_whitespace()) {
+        let ch = ch.to_ascii_lowercase();
+        let index = start
+            + candidate[start..]
+                .iter()
+                .position(|other| other.to_ascii_lowercase() == ch)?;
+
+        score += MATCH_SCORE;
+
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += CONSECUTIVE_BONUS;
+        }
+
+        if index == 0 || !candidate[index - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+
+        previous = Some(index);
+        start = index + 1;
+    }
+
+    Some(score)
+}
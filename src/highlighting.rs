@@ -0,0 +1,38 @@
+use crossterm::style::Color;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    None,
+    Number,
+    String,
+    Character,
+    Comment,
+    Keyword,
+    Typename,
+    Match,
+    Selection,
+    Bracket,
+}
+
+impl Type {
+    pub fn to_color(self) -> Color {
+        match self {
+            Type::None => Color::Reset,
+            Type::Number => Color::Magenta,
+            Type::String | Type::Character => Color::Green,
+            Type::Comment => Color::DarkGrey,
+            Type::Keyword => Color::Yellow,
+            Type::Typename => Color::Cyan,
+            Type::Match => Color::Black,
+            Type::Selection | Type::Bracket => Color::White,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Normal,
+    Comment,
+    String(usize),
+}
@@ -0,0 +1,296 @@
+use crate::Position;
+use std::cmp;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Clone)]
+pub enum Edit {
+    Insert { at: Position, text: String },
+    Delete { at: Position, text: String },
+}
+
+impl Edit {
+    pub fn inverse(&self) -> Edit {
+        match self {
+            Edit::Insert { at, text } => Edit::Delete {
+                at: *at,
+                text: text.clone(),
+            },
+            Edit::Delete { at, text } => Edit::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+        }
+    }
+
+    pub fn shift(&self, position: &Position) -> Position {
+        let (at, lines, end) = self.span();
+        let before = |a: &Position, b: &Position| (a.y, a.x) < (b.y, b.x);
+
+        match self {
+            Edit::Insert { .. } if before(position, at) => *position,
+            Edit::Insert { .. } if position.y == at.y => {
+                Position::new(end.x + position.x - at.x, end.y)
+            }
+            Edit::Insert { .. } => Position::new(position.x, position.y + lines),
+            Edit::Delete { .. } if !before(at, position) => *position,
+            Edit::Delete { .. } if before(position, &end) => *at,
+            Edit::Delete { .. } if position.y == end.y => {
+                Position::new(at.x + position.x - end.x, at.y)
+            }
+            Edit::Delete { .. } => Position::new(position.x, position.y - lines),
+        }
+    }
+
+    pub fn shift_line(&self, y: usize) -> usize {
+        let (at, lines, _) = self.span();
+
+        match self {
+            _ if y <= at.y => y,
+            Edit::Insert { .. } => y + lines,
+            Edit::Delete { .. } => cmp::max(y.saturating_sub(lines), at.y),
+        }
+    }
+
+    fn span(&self) -> (&Position, usize, Position) {
+        let (Edit::Insert { at, text } | Edit::Delete { at, text }) = self;
+        let (lines, tail) = match text.rsplit_once('\n') {
+            Some((head, tail)) => (head.matches('\n').count() + 1, tail),
+            None => (0, text.as_str()),
+        };
+        let tail = tail.graphemes(true).count();
+
+        let end = if lines == 0 {
+            Position::new(at.x + tail, at.y)
+        } else {
+            Position::new(tail, at.y + lines)
+        };
+
+        (at, lines, end)
+    }
+
+    fn merge(&mut self, next: &Edit) -> bool {
+        match (self, next) {
+            (
+                Edit::Insert { at, text },
+                Edit::Insert {
+                    at: next_at,
+                    text: next_text,
+                },
+            ) => {
+                if text.contains('\n') || next_text.contains('\n') {
+                    return false;
+                }
+
+                let end = at.x + text.graphemes(true).count();
+
+                if next_at.y == at.y && next_at.x == end {
+                    text.push_str(next_text);
+                    true
+                } else {
+                    false
+                }
+            }
+            (
+                Edit::Delete { at, text },
+                Edit::Delete {
+                    at: next_at,
+                    text: next_text,
+                },
+            ) => {
+                if text.contains('\n') || next_text.contains('\n') || next_at.y != at.y {
+                    return false;
+                }
+
+                if next_at.x == at.x {
+                    text.push_str(next_text);
+                    true
+                } else if next_at.x + next_text.graphemes(true).count() == at.x {
+                    text.insert_str(0, next_text);
+                    at.x = next_at.x;
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct History {
+    undo: Vec<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+    sealed: bool,
+}
+
+impl History {
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+
+        if !self.sealed {
+            if let Some(last) = self.undo.last_mut().and_then(|group| group.last_mut()) {
+                if last.merge(&edit) {
+                    return;
+                }
+            }
+        }
+
+        self.sealed = false;
+        self.undo.push(vec![edit]);
+    }
+
+    pub fn record_group(&mut self, edits: Vec<Edit>) {
+        self.redo.clear();
+        self.sealed = true;
+        self.undo.push(edits);
+    }
+
+    pub fn undo(&mut self) -> Option<Vec<Edit>> {
+        let group = self.undo.pop()?;
+        self.redo.push(group.clone());
+        self.sealed = true;
+
+        Some(group)
+    }
+
+    pub fn redo(&mut self) -> Option<Vec<Edit>> {
+        let group = self.redo.pop()?;
+        self.undo.push(group.clone());
+        self.sealed = true;
+
+        Some(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(x: usize, y: usize, text: &str) -> Edit {
+        Edit::Insert {
+            at: Position::new(x, y),
+            text: text.to_string(),
+        }
+    }
+
+    fn delete(x: usize, y: usize, text: &str) -> Edit {
+        Edit::Delete {
+            at: Position::new(x, y),
+            text: text.to_string(),
+        }
+    }
+
+    fn texts(group: &[Edit]) -> Vec<(bool, usize, usize, &str)> {
+        group
+            .iter()
+            .map(|edit| match edit {
+                Edit::Insert { at, text } => (true, at.x, at.y, text.as_str()),
+                Edit::Delete { at, text } => (false, at.x, at.y, text.as_str()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn consecutive_typing_is_one_step() {
+        let mut history = History::default();
+        history.record(insert(0, 0, "a"));
+        history.record(insert(1, 0, "é"));
+        history.record(insert(2, 0, "c"));
+
+        assert_eq!(texts(&history.undo().unwrap()), [(true, 0, 0, "aéc")]);
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn newlines_and_jumps_start_new_steps() {
+        let mut history = History::default();
+        history.record(insert(0, 0, "a"));
+        history.record(insert(1, 0, "\n"));
+        history.record(insert(0, 1, "b"));
+        history.record(insert(5, 1, "c"));
+
+        assert_eq!(texts(&history.undo().unwrap()), [(true, 5, 1, "c")]);
+        assert_eq!(texts(&history.undo().unwrap()), [(true, 0, 1, "b")]);
+        assert_eq!(texts(&history.undo().unwrap()), [(true, 1, 0, "\n")]);
+        assert_eq!(texts(&history.undo().unwrap()), [(true, 0, 0, "a")]);
+    }
+
+    #[test]
+    fn backspace_and_delete_merge_in_both_directions() {
+        let mut history = History::default();
+        history.record(delete(3, 0, "c"));
+        history.record(delete(2, 0, "b"));
+        history.record(delete(2, 0, "d"));
+
+        assert_eq!(texts(&history.undo().unwrap()), [(false, 2, 0, "bcd")]);
+    }
+
+    #[test]
+    fn undo_seals_the_last_step_and_redo_replays_it() {
+        let mut history = History::default();
+        history.record(insert(0, 0, "a"));
+        history.undo();
+        assert_eq!(texts(&history.redo().unwrap()), [(true, 0, 0, "a")]);
+
+        history.record(insert(1, 0, "b"));
+        assert_eq!(texts(&history.undo().unwrap()), [(true, 1, 0, "b")]);
+        assert_eq!(texts(&history.undo().unwrap()), [(true, 0, 0, "a")]);
+    }
+
+    #[test]
+    fn new_edits_clear_redo() {
+        let mut history = History::default();
+        history.record(insert(0, 0, "a"));
+        history.undo();
+        history.record_group(vec![insert(0, 0, "b"), delete(0, 0, "b")]);
+
+        assert!(history.redo().is_none());
+        assert_eq!(history.undo().unwrap().len(), 2);
+    }
+
+    fn shifted(edit: &Edit, x: usize, y: usize) -> (usize, usize) {
+        let position = edit.shift(&Position::new(x, y));
+        (position.x, position.y)
+    }
+
+    #[test]
+    fn insert_shifts_positions_after_it() {
+        let edit = insert(2, 1, "ab\ncd\ne");
+
+        assert_eq!(shifted(&edit, 5, 0), (5, 0));
+        assert_eq!(shifted(&edit, 1, 1), (1, 1));
+        assert_eq!(shifted(&edit, 2, 1), (1, 3));
+        assert_eq!(shifted(&edit, 4, 1), (3, 3));
+        assert_eq!(shifted(&edit, 0, 2), (0, 4));
+        assert_eq!(shifted(&insert(1, 0, "xy"), 3, 0), (5, 0));
+        assert_eq!(edit.shift_line(1), 1);
+        assert_eq!(edit.shift_line(2), 4);
+    }
+
+    #[test]
+    fn delete_shifts_positions_after_it() {
+        let edit = delete(2, 1, "ab\ncd\ne");
+
+        assert_eq!(shifted(&edit, 5, 0), (5, 0));
+        assert_eq!(shifted(&edit, 2, 1), (2, 1));
+        assert_eq!(shifted(&edit, 1, 2), (2, 1));
+        assert_eq!(shifted(&edit, 0, 3), (2, 1));
+        assert_eq!(shifted(&edit, 1, 3), (2, 1));
+        assert_eq!(shifted(&edit, 4, 3), (5, 1));
+        assert_eq!(shifted(&edit, 7, 4), (7, 2));
+        assert_eq!(shifted(&delete(1, 0, "xy"), 5, 0), (3, 0));
+        assert_eq!(edit.shift_line(1), 1);
+        assert_eq!(edit.shift_line(2), 1);
+        assert_eq!(edit.shift_line(3), 1);
+        assert_eq!(edit.shift_line(6), 4);
+    }
+
+    #[test]
+    fn inverse_swaps_insert_and_delete() {
+        assert_eq!(
+            texts(&[insert(1, 2, "x").inverse(), delete(3, 4, "y").inverse()]),
+            [(false, 1, 2, "x"), (true, 3, 4, "y")]
+        );
+    }
+}
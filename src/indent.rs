@@ -0,0 +1,71 @@
+use std::fmt;
+
+const DETECT_LINES: usize = 1000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Tabs,
+    Spaces(usize),
+}
+
+impl Indent {
+    pub fn detect<'a>(lines: impl Iterator<Item = &'a str>) -> Option<Indent> {
+        let mut tabs = 0;
+        let mut spaces = 0;
+        let mut deltas = [0usize; 9];
+        let mut previous = 0;
+
+        for line in lines.take(DETECT_LINES) {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with('\t') {
+                tabs += 1;
+                previous = 0;
+                continue;
+            }
+
+            let indent = line.chars().take_while(|ch| *ch == ' ').count();
+
+            if indent > 0 {
+                spaces += 1;
+            }
+
+            let delta = indent.abs_diff(previous);
+            if (1..deltas.len()).contains(&delta) {
+                deltas[delta] += 1;
+            }
+
+            previous = indent;
+        }
+
+        if tabs > spaces {
+            return Some(Indent::Tabs);
+        }
+
+        deltas
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, count)| **count > 0)
+            .max_by_key(|(width, count)| (**count, usize::MAX - width))
+            .map(|(width, _)| Indent::Spaces(width))
+    }
+
+    pub fn unit(self) -> String {
+        match self {
+            Indent::Tabs => "\t".to_string(),
+            Indent::Spaces(width) => " ".repeat(width),
+        }
+    }
+}
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Indent::Tabs => write!(f, "Tabs"),
+            Indent::Spaces(width) => write!(f, "Spaces:{width}"),
+        }
+    }
+}
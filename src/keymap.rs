@@ -0,0 +1,237 @@
+use crate::Command;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+const DEFAULT_BINDINGS: &[(&str, Command)] = &[
+    ("ctrl-q", Command::Quit),
+    ("ctrl-s", Command::Save),
+    ("ctrl-f", Command::Find),
+    ("ctrl-g", Command::RegexFind),
+    ("ctrl-r", Command::Replace),
+    ("ctrl-o", Command::Open),
+    ("ctrl-b", Command::PickBuffer),
+    ("alt-right", Command::NextBuffer),
+    ("alt-left", Command::PreviousBuffer),
+    ("ctrl-e", Command::ConvertLineEndings),
+    ("ctrl-z", Command::Undo),
+    ("ctrl-y", Command::Redo),
+    ("up", Command::MoveUp),
+    ("down", Command::MoveDown),
+    ("left", Command::MoveLeft),
+    ("right", Command::MoveRight),
+    ("shift-up", Command::SelectUp),
+    ("shift-down", Command::SelectDown),
+    ("shift-left", Command::SelectLeft),
+    ("shift-right", Command::SelectRight),
+    ("ctrl-x", Command::Cut),
+    ("ctrl-c", Command::Copy),
+    ("ctrl-v", Command::Paste),
+    ("enter", Command::InsertNewline),
+    ("tab", Command::InsertTab),
+    ("ctrl-t", Command::IndentLines),
+    ("ctrl-d", Command::DedentLines),
+    ("backtab", Command::DedentLines),
+    ("delete", Command::Delete),
+    ("backspace", Command::Backspace),
+    ("ctrl-w s", Command::SplitHorizontal),
+    ("ctrl-w v", Command::SplitVertical),
+    ("ctrl-w c", Command::ClosePane),
+    ("ctrl-w w", Command::NextPane),
+    ("ctrl-w up", Command::FocusUp),
+    ("ctrl-w down", Command::FocusDown),
+    ("ctrl-w left", Command::FocusLeft),
+    ("ctrl-w right", Command::FocusRight),
+    ("ctrl-w +", Command::GrowHeight),
+    ("ctrl-w -", Command::ShrinkHeight),
+    ("ctrl-w >", Command::GrowWidth),
+    ("ctrl-w <", Command::ShrinkWidth),
+    ("ctrl-p", Command::CommandPalette),
+    ("ctrl-l", Command::GotoLine),
+    ("alt-m", Command::MatchBracket),
+];
+
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::Home, "home"),
+    (KeyCode::End, "end"),
+    (KeyCode::PageUp, "pageup"),
+    (KeyCode::PageDown, "pagedown"),
+    (KeyCode::Enter, "enter"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::BackTab, "backtab"),
+    (KeyCode::Backspace, "backspace"),
+    (KeyCode::Delete, "delete"),
+    (KeyCode::Insert, "insert"),
+    (KeyCode::Esc, "esc"),
+    (KeyCode::Char(' '), "space"),
+];
+
+pub enum Lookup {
+    Command(Command),
+    Prefix,
+    Unbound,
+}
+
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<Vec<KeyEvent>, Command>,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .filter_map(|(keys, command)| Some((parse_keys(keys)?, *command)))
+            .collect();
+
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn bind(&mut self, keys: Vec<KeyEvent>, command: Command) {
+        self.bindings.insert(keys, command);
+    }
+
+    pub fn unbind(&mut self, keys: &[KeyEvent]) {
+        self.bindings.remove(keys);
+    }
+
+    pub fn lookup(&self, keys: &[KeyEvent]) -> Lookup {
+        if self
+            .bindings
+            .keys()
+            .any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+        {
+            Lookup::Prefix
+        } else if let Some(command) = self.bindings.get(keys) {
+            Lookup::Command(*command)
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    pub fn keys(&self, command: Command) -> Option<&[KeyEvent]> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == command)
+            .map(|(keys, _)| keys.as_slice())
+            .min_by_key(|keys| (keys.len(), format_keys(keys)))
+    }
+
+    pub fn continuations(&self, keys: &[KeyEvent]) -> Vec<(String, Command)> {
+        let mut continuations: Vec<(String, Command)> = self
+            .bindings
+            .iter()
+            .filter(|(sequence, _)| sequence.len() > keys.len() && sequence.starts_with(keys))
+            .map(|(sequence, command)| (format_keys(&sequence[keys.len()..]), *command))
+            .collect();
+
+        continuations.sort_by_key(|(_, command)| *command);
+        continuations
+    }
+}
+
+pub fn normalize(key: KeyEvent) -> KeyEvent {
+    match key.code {
+        KeyCode::Char(_) | KeyCode::BackTab => {
+            KeyEvent::new(key.code, key.modifiers - KeyModifiers::SHIFT)
+        }
+        _ => KeyEvent::new(key.code, key.modifiers),
+    }
+}
+
+pub fn parse_keys(text: &str) -> Option<Vec<KeyEvent>> {
+    let keys: Option<Vec<KeyEvent>> = text.split_whitespace().map(parse_key).collect();
+
+    keys.filter(|keys| !keys.is_empty())
+}
+
+fn parse_key(text: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = text;
+
+    loop {
+        if let Some(key) = strip_modifier(rest, "ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = key;
+        } else if let Some(key) = strip_modifier(rest, "alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = key;
+        } else if let Some(key) = strip_modifier(rest, "shift-") {
+            modifiers |= KeyModifiers::SHIFT;
+            rest = key;
+        } else {
+            break;
+        }
+    }
+
+    let lower = rest.to_lowercase();
+    let mut chars = rest.chars();
+
+    let code = if let Some((code, _)) = KEY_NAMES.iter().find(|(_, name)| *name == lower) {
+        *code
+    } else if let (Some(ch), None) = (chars.next(), chars.next()) {
+        if modifiers.contains(KeyModifiers::CONTROL) {
+            KeyCode::Char(ch.to_ascii_lowercase())
+        } else if modifiers.contains(KeyModifiers::SHIFT) {
+            KeyCode::Char(ch.to_ascii_uppercase())
+        } else {
+            KeyCode::Char(ch)
+        }
+    } else {
+        let number = lower.strip_prefix('f')?.parse::<u8>().ok()?;
+        if !(1..=12).contains(&number) {
+            return None;
+        }
+        KeyCode::F(number)
+    };
+
+    Some(normalize(KeyEvent::new(code, modifiers)))
+}
+
+fn strip_modifier<'a>(text: &'a str, modifier: &str) -> Option<&'a str> {
+    let rest = text.get(modifier.len()..)?;
+
+    if text[..modifier.len()].eq_ignore_ascii_case(modifier) && !rest.is_empty() {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+pub fn format_keys(keys: &[KeyEvent]) -> String {
+    keys.iter()
+        .map(format_key)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn format_key(key: &KeyEvent) -> String {
+    let mut text = String::new();
+
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        text.push_str("ctrl-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        text.push_str("alt-");
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        text.push_str("shift-");
+    }
+
+    match (
+        KEY_NAMES.iter().find(|(code, _)| *code == key.code),
+        key.code,
+    ) {
+        (Some((_, name)), _) => text.push_str(name),
+        (None, KeyCode::Char(ch)) => text.push(ch),
+        (None, KeyCode::F(number)) => text.push_str(&format!("f{number}")),
+        _ => text.push('?'),
+    }
+
+    text
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumbers {
+    #[default]
+    Off,
+    Absolute,
+    Relative,
+    Hybrid,
+}
+
+impl LineNumbers {
+    pub fn from_name(name: &str) -> Option<LineNumbers> {
+        match name {
+            "off" => Some(LineNumbers::Off),
+            "absolute" => Some(LineNumbers::Absolute),
+            "relative" => Some(LineNumbers::Relative),
+            "hybrid" => Some(LineNumbers::Hybrid),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> LineNumbers {
+        match self {
+            LineNumbers::Off => LineNumbers::Absolute,
+            LineNumbers::Absolute => LineNumbers::Relative,
+            LineNumbers::Relative => LineNumbers::Hybrid,
+            LineNumbers::Hybrid => LineNumbers::Off,
+        }
+    }
+
+    pub fn label(self, line: usize, current: usize) -> usize {
+        match self {
+            LineNumbers::Relative => line.abs_diff(current),
+            LineNumbers::Hybrid if line != current => line.abs_diff(current),
+            _ => line + 1,
+        }
+    }
+}
+
+impl fmt::Display for LineNumbers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineNumbers::Off => write!(f, "off"),
+            LineNumbers::Absolute => write!(f, "absolute"),
+            LineNumbers::Relative => write!(f, "relative"),
+            LineNumbers::Hybrid => write!(f, "hybrid"),
+        }
+    }
+}
@@ -1,17 +1,48 @@
 #![allow(clippy::len_without_is_empty)]
 
+mod buffer;
+mod command;
+mod config;
+mod config_error;
 mod document;
+mod document_error;
 mod editor;
+mod file_type;
+mod fuzzy;
+mod highlighting;
+mod history;
+mod indent;
+mod keymap;
+mod line_ending;
+mod line_numbers;
 mod position;
+mod register;
+mod rope;
 mod row;
+mod screen;
 mod size;
 mod status_message;
 mod terminal;
+mod vi;
+mod window;
+mod wrap;
 
+pub use buffer::Buffer;
+pub use command::Command;
+pub use config::Config;
+pub use config_error::ConfigError;
 pub use document::Document;
+pub use document_error::DocumentError;
 use editor::Editor;
+pub use editor::SearchDirection;
+pub use indent::Indent;
+pub use keymap::Keymap;
+pub use line_ending::LineEnding;
+pub use line_numbers::LineNumbers;
 pub use position::Position;
+pub use register::Register;
 pub use row::Row;
+pub use screen::Screen;
 pub use size::Size;
 pub use status_message::StatusMessage;
 pub use terminal::Terminal;
@@ -1,11 +1,11 @@
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
-    pub x: u16,
-    pub y: u16,
+    pub x: usize,
+    pub y: usize,
 }
 
 impl Position {
-    pub fn new(x: u16, y: u16) -> Position {
+    pub fn new(x: usize, y: usize) -> Position {
         Position { x, y }
     }
 }
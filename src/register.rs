@@ -0,0 +1,5 @@
+#[derive(Default, Clone)]
+pub struct Register {
+    pub text: String,
+    pub linewise: bool,
+}
@@ -0,0 +1,473 @@
+use std::ops::{Index, IndexMut};
+use std::{mem, slice};
+
+const MAX_LEAF: usize = 64;
+const MAX_CHILDREN: usize = 16;
+
+#[derive(Clone)]
+pub struct Rope<T> {
+    root: Node<T>,
+}
+
+#[derive(Clone)]
+enum Node<T> {
+    Leaf(Vec<T>),
+    Internal { children: Vec<Node<T>>, len: usize },
+}
+
+impl<T> Default for Rope<T> {
+    fn default() -> Rope<T> {
+        Rope {
+            root: Node::Leaf(Vec::new()),
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Rope<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Rope<T> {
+        let mut nodes: Vec<Node<T>> = Vec::new();
+        let mut leaf = Vec::with_capacity(MAX_LEAF / 2);
+
+        for item in iter {
+            leaf.push(item);
+
+            if leaf.len() == MAX_LEAF / 2 {
+                nodes.push(Node::Leaf(mem::replace(
+                    &mut leaf,
+                    Vec::with_capacity(MAX_LEAF / 2),
+                )));
+            }
+        }
+
+        if !leaf.is_empty() {
+            nodes.push(Node::Leaf(leaf));
+        }
+
+        Rope::from_nodes(nodes)
+    }
+}
+
+impl<T> Rope<T> {
+    fn from_nodes(mut nodes: Vec<Node<T>>) -> Rope<T> {
+        while nodes.len() > 1 {
+            let mut parents = Vec::with_capacity(nodes.len() / (MAX_CHILDREN / 2) + 1);
+            let mut children = nodes.into_iter().peekable();
+
+            while children.peek().is_some() {
+                let group: Vec<Node<T>> = children.by_ref().take(MAX_CHILDREN / 2).collect();
+                let len = group.iter().map(Node::len).sum();
+                parents.push(Node::Internal {
+                    children: group,
+                    len,
+                });
+            }
+
+            nodes = parents;
+        }
+
+        Rope {
+            root: nodes.pop().unwrap_or(Node::Leaf(Vec::new())),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len() {
+            Some(self.root.get(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len() {
+            Some(self.root.get_mut(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.insert(self.len(), item);
+    }
+
+    pub fn insert(&mut self, index: usize, item: T) {
+        assert!(index <= self.len(), "insertion index out of bounds");
+
+        self.root.insert(index, item);
+
+        if self.root.is_overflowing() {
+            let right = self.root.split_off();
+            let left = mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            let len = left.len() + right.len();
+
+            self.root = Node::Internal {
+                children: vec![left, right],
+                len,
+            };
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len(), "removal index out of bounds");
+
+        let item = self.root.remove(index);
+
+        while let Node::Internal { children, .. } = &mut self.root {
+            match children.len() {
+                0 => self.root = Node::Leaf(Vec::new()),
+                1 => self.root = children.pop().unwrap(),
+                _ => break,
+            }
+        }
+
+        item
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        match &self.root {
+            Node::Leaf(items) => Iter {
+                stack: Vec::new(),
+                items: items.iter(),
+            },
+            Node::Internal { children, .. } => Iter {
+                stack: vec![children.iter()],
+                items: [].iter(),
+            },
+        }
+    }
+}
+
+impl<T> Node<T> {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(items) => items.len(),
+            Node::Internal { len, .. } => *len,
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            Node::Leaf(items) => items.len(),
+            Node::Internal { children, .. } => children.len(),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        match self {
+            Node::Leaf(_) => MAX_LEAF,
+            Node::Internal { .. } => MAX_CHILDREN,
+        }
+    }
+
+    fn is_overflowing(&self) -> bool {
+        self.width() > self.capacity()
+    }
+
+    fn is_underfull(&self) -> bool {
+        self.width() < self.capacity() / 4
+    }
+
+    fn get(&self, index: usize) -> &T {
+        match self {
+            Node::Leaf(items) => &items[index],
+            Node::Internal { children, .. } => {
+                let (child, index) = locate(children, index);
+                children[child].get(index)
+            }
+        }
+    }
+
+    fn get_mut(&mut self, index: usize) -> &mut T {
+        match self {
+            Node::Leaf(items) => &mut items[index],
+            Node::Internal { children, .. } => {
+                let (child, index) = locate(children, index);
+                children[child].get_mut(index)
+            }
+        }
+    }
+
+    fn insert(&mut self, index: usize, item: T) {
+        match self {
+            Node::Leaf(items) => items.insert(index, item),
+            Node::Internal { children, len } => {
+                let (child, index) = if index == *len {
+                    let last = children.len() - 1;
+                    (last, children[last].len())
+                } else {
+                    locate(children, index)
+                };
+
+                children[child].insert(index, item);
+                *len += 1;
+
+                if children[child].is_overflowing() {
+                    let right = children[child].split_off();
+                    children.insert(child + 1, right);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        match self {
+            Node::Leaf(items) => items.remove(index),
+            Node::Internal { children, len } => {
+                let (child, index) = locate(children, index);
+                let item = children[child].remove(index);
+                *len -= 1;
+
+                if children[child].len() == 0 {
+                    children.remove(child);
+                } else if children[child].is_underfull() {
+                    let sibling = if child + 1 < children.len() {
+                        child + 1
+                    } else {
+                        child.saturating_sub(1)
+                    };
+                    let (left, right) = (child.min(sibling), child.max(sibling));
+
+                    if left != right
+                        && children[left].width() + children[right].width()
+                            <= children[left].capacity()
+                    {
+                        let right = children.remove(right);
+                        children[left].append(right);
+                    }
+                }
+
+                item
+            }
+        }
+    }
+
+    fn split_off(&mut self) -> Node<T> {
+        match self {
+            Node::Leaf(items) => Node::Leaf(items.split_off(items.len() / 2)),
+            Node::Internal { children, len } => {
+                let right = children.split_off(children.len() / 2);
+                let right_len = right.iter().map(Node::len).sum();
+                *len -= right_len;
+
+                Node::Internal {
+                    children: right,
+                    len: right_len,
+                }
+            }
+        }
+    }
+
+    fn append(&mut self, other: Node<T>) {
+        match (self, other) {
+            (Node::Leaf(items), Node::Leaf(mut other)) => items.append(&mut other),
+            (
+                Node::Internal { children, len },
+                Node::Internal {
+                    children: mut other,
+                    len: other_len,
+                },
+            ) => {
+                children.append(&mut other);
+                *len += other_len;
+            }
+            _ => unreachable!("siblings are always at the same depth"),
+        }
+    }
+}
+
+fn locate<T>(children: &[Node<T>], mut index: usize) -> (usize, usize) {
+    for (i, child) in children.iter().enumerate() {
+        if index < child.len() {
+            return (i, index);
+        }
+
+        index -= child.len();
+    }
+
+    unreachable!("index is always checked against the node length")
+}
+
+pub struct Iter<'a, T> {
+    stack: Vec<slice::Iter<'a, Node<T>>>,
+    items: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(item);
+            }
+
+            let node = loop {
+                match self.stack.last_mut()?.next() {
+                    Some(node) => break node,
+                    None => {
+                        self.stack.pop();
+                    }
+                }
+            };
+
+            match node {
+                Node::Leaf(items) => self.items = items.iter(),
+                Node::Internal { children, .. } => self.stack.push(children.iter()),
+            }
+        }
+    }
+}
+
+impl<T> Index<usize> for Rope<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for Rope<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Rope<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    fn depth(node: &Node<usize>) -> usize {
+        match node {
+            Node::Leaf(items) => {
+                assert!(items.len() <= MAX_LEAF);
+                0
+            }
+            Node::Internal { children, len } => {
+                assert!(!children.is_empty() && children.len() <= MAX_CHILDREN);
+                assert_eq!(*len, children.iter().map(Node::len).sum::<usize>());
+
+                let depths: Vec<usize> = children.iter().map(depth).collect();
+                assert!(depths.iter().all(|d| *d == depths[0]));
+                depths[0] + 1
+            }
+        }
+    }
+
+    fn assert_same(rope: &Rope<usize>, vec: &[usize]) {
+        depth(&rope.root);
+        assert_eq!(rope.len(), vec.len());
+        assert!(rope.iter().eq(vec.iter()));
+        assert!((0..vec.len()).all(|i| rope[i] == vec[i]));
+        assert_eq!(rope.get(vec.len()), None);
+        assert_eq!(rope.last(), vec.last());
+    }
+
+    #[test]
+    fn from_iter_matches_vec() {
+        for len in [
+            0,
+            1,
+            MAX_LEAF / 2,
+            MAX_LEAF,
+            MAX_LEAF * MAX_CHILDREN + 1,
+            20_000,
+        ] {
+            let vec: Vec<usize> = (0..len).collect();
+            assert_same(&vec.iter().copied().collect(), &vec);
+        }
+    }
+
+    #[test]
+    fn push_and_pop_across_split_boundaries() {
+        let mut rope = Rope::default();
+        let mut vec = Vec::new();
+        let len = MAX_LEAF * MAX_CHILDREN * MAX_CHILDREN + 1;
+
+        for i in 0..len {
+            rope.push(i);
+            vec.push(i);
+        }
+        assert_same(&rope, &vec);
+
+        while !vec.is_empty() {
+            assert_eq!(rope.remove(rope.len() - 1), vec.pop().unwrap());
+        }
+        assert_same(&rope, &vec);
+    }
+
+    #[test]
+    fn front_inserts_and_removes() {
+        let mut rope = Rope::default();
+        let mut vec = Vec::new();
+
+        for i in 0..MAX_LEAF * MAX_CHILDREN * 3 {
+            rope.insert(0, i);
+            vec.insert(0, i);
+        }
+        assert_same(&rope, &vec);
+
+        while !vec.is_empty() {
+            assert_eq!(rope.remove(0), vec.remove(0));
+        }
+        assert_same(&rope, &vec);
+    }
+
+    #[test]
+    fn random_operations_match_vec() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for round in 0..20 {
+            let start = rng.next(MAX_LEAF * MAX_CHILDREN * 2);
+            let mut vec: Vec<usize> = (0..start).collect();
+            let mut rope: Rope<usize> = vec.iter().copied().collect();
+
+            for step in 0..5_000 {
+                let grow = vec.is_empty() || rng.next(100) < if round % 2 == 0 { 60 } else { 40 };
+
+                if grow {
+                    let index = rng.next(vec.len() + 1);
+                    rope.insert(index, step);
+                    vec.insert(index, step);
+                } else {
+                    let index = rng.next(vec.len());
+                    assert_eq!(rope.remove(index), vec.remove(index));
+                }
+
+                if let Some(index) = vec.len().checked_sub(1).map(|last| rng.next(last + 1)) {
+                    rope[index] += 1;
+                    vec[index] += 1;
+                }
+            }
+
+            assert_same(&rope, &vec);
+        }
+    }
+}
@@ -1,14 +1,39 @@
-use std::{cmp, fmt};
+use crate::file_type::HighlightingOptions;
+use crate::highlighting::{self, State};
+use crate::LineEnding;
+use crate::SearchDirection;
+use regex::{Captures, Regex};
+use std::fmt;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+fn grapheme_width(grapheme: &str, column: usize, tab_size: usize) -> usize {
+    if grapheme == "\t" {
+        let tab_size = tab_size.max(1);
+        tab_size - column % tab_size
+    } else {
+        grapheme.width()
+    }
+}
 
 #[derive(Default, Clone)]
 pub struct Row {
     string: String,
+    len: usize,
+    highlighting: Vec<highlighting::Type>,
+    state: State,
+    ending: LineEnding,
 }
 
 impl From<&str> for Row {
     fn from(string: &str) -> Row {
         Row {
             string: String::from(string),
+            len: string.graphemes(true).count(),
+            highlighting: Vec::new(),
+            state: State::default(),
+            ending: LineEnding::default(),
         }
     }
 }
@@ -20,41 +45,590 @@ impl fmt::Display for Row {
 }
 
 impl Row {
-    pub fn render(&self, start: usize, end: usize) -> &str {
-        &self.string[cmp::min(start, self.len())..cmp::min(end, self.len())]
+    pub fn render(&self, start: usize, end: usize, tab_size: usize) -> String {
+        self.render_spans(start, end, &[], tab_size)
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect()
+    }
+
+    pub fn render_spans(
+        &self,
+        start: usize,
+        end: usize,
+        marks: &[(Range<usize>, highlighting::Type)],
+        tab_size: usize,
+    ) -> Vec<(highlighting::Type, String)> {
+        let mut spans: Vec<(highlighting::Type, String)> = Vec::new();
+        let mut column = 0;
+
+        for (i, grapheme) in self.string.graphemes(true).enumerate() {
+            if column >= end {
+                break;
+            }
+
+            let width = grapheme_width(grapheme, column, tab_size);
+            let highlight =
+                if let Some((_, mark)) = marks.iter().find(|(range, _)| range.contains(&i)) {
+                    *mark
+                } else {
+                    self.highlighting
+                        .get(i)
+                        .copied()
+                        .unwrap_or(highlighting::Type::None)
+                };
+
+            let text = if column >= start && column + width <= end {
+                if grapheme == "\t" {
+                    " ".repeat(width)
+                } else {
+                    grapheme.to_string()
+                }
+            } else if column + width > start {
+                " ".repeat((column + width).min(end) - column.max(start))
+            } else {
+                String::new()
+            };
+
+            match spans.last_mut() {
+                Some((last, span)) if *last == highlight => span.push_str(&text),
+                _ if !text.is_empty() => spans.push((highlight, text)),
+                _ => {}
+            }
+
+            column += width;
+        }
+
+        spans
     }
 
     pub fn len(&self) -> usize {
-        self.string.len()
+        self.len
+    }
+
+    pub fn width(&self, tab_size: usize) -> usize {
+        self.column(self.len, tab_size)
+    }
+
+    pub fn column(&self, at: usize, tab_size: usize) -> usize {
+        self.string
+            .graphemes(true)
+            .take(at)
+            .fold(0, |column, grapheme| {
+                column + grapheme_width(grapheme, column, tab_size)
+            })
+    }
+
+    pub fn index(&self, column: usize, tab_size: usize) -> usize {
+        let mut current = 0;
+
+        for (i, grapheme) in self.string.graphemes(true).enumerate() {
+            current += grapheme_width(grapheme, current, tab_size);
+
+            if current > column {
+                return i;
+            }
+        }
+
+        self.len
+    }
+
+    pub fn slice(&self, start: usize, end: usize) -> &str {
+        let start = self.byte_index(start);
+        let end = self.byte_index(end).max(start);
+
+        &self.string[start..end]
+    }
+
+    pub fn graphemes(&self) -> Vec<&str> {
+        self.string.graphemes(true).collect()
+    }
+
+    pub fn wrap(&self, width: usize, continuation: usize, tab_size: usize) -> Vec<Range<usize>> {
+        let graphemes = self.graphemes();
+        let mut columns = Vec::with_capacity(graphemes.len() + 1);
+        let mut column = 0;
+
+        for grapheme in &graphemes {
+            columns.push(column);
+            column += grapheme_width(grapheme, column, tab_size);
+        }
+        columns.push(column);
+
+        let mut lines = Vec::new();
+        let mut limit = width.max(1);
+        let mut start = 0;
+        let mut breakpoint = None;
+        let mut i = 0;
+
+        while i < graphemes.len() {
+            if columns[i + 1] - columns[start] > limit && i > start {
+                let end = breakpoint.filter(|end| *end > start).unwrap_or(i);
+
+                lines.push(start..end);
+                start = end;
+                limit = continuation.max(1);
+                breakpoint = None;
+                continue;
+            }
+
+            if graphemes[i].chars().all(char::is_whitespace) {
+                breakpoint = Some(i + 1);
+            }
+
+            i += 1;
+        }
+
+        lines.push(start..graphemes.len());
+
+        if !graphemes.is_empty() && columns[graphemes.len()] - columns[start] >= limit {
+            lines.push(graphemes.len()..graphemes.len());
+        }
+
+        lines
+    }
+
+    pub fn word_bounds(&self, at: usize) -> (usize, usize) {
+        let graphemes = self.graphemes();
+        let class = |grapheme: &&str| {
+            if grapheme.chars().all(char::is_whitespace) {
+                0
+            } else if grapheme.chars().all(|ch| ch.is_alphanumeric() || ch == '_') {
+                1
+            } else {
+                2
+            }
+        };
+
+        let target = match graphemes.get(at) {
+            Some(grapheme) => class(grapheme),
+            None => return (at, at),
+        };
+
+        let before = graphemes[..at]
+            .iter()
+            .rev()
+            .take_while(|grapheme| class(grapheme) == target)
+            .count();
+        let after = graphemes[at..]
+            .iter()
+            .take_while(|grapheme| class(grapheme) == target)
+            .count();
+
+        (at - before, at + after)
+    }
+
+    pub fn is_code(&self, at: usize) -> bool {
+        !matches!(
+            self.highlighting.get(at),
+            Some(
+                highlighting::Type::String
+                    | highlighting::Type::Character
+                    | highlighting::Type::Comment
+            )
+        )
+    }
+
+    pub fn grapheme(&self, at: usize) -> Option<&str> {
+        self.string.graphemes(true).nth(at)
     }
 
     pub fn insert(&mut self, ch: char, at: usize) {
-        self.string.insert(at, ch);
+        self.insert_str(ch.encode_utf8(&mut [0; 4]), at);
+    }
+
+    pub fn insert_str(&mut self, string: &str, at: usize) {
+        let byte = self.byte_index(at);
+        self.string.insert_str(byte, string);
+        self.update_len();
     }
 
     pub fn delete(&mut self, at: usize) {
-        if self.string.chars().nth(at).is_some() {
-            self.string.remove(at);
+        if at < self.len {
+            let start = self.byte_index(at);
+            let end = self.byte_index(at + 1);
+            self.string.replace_range(start..end, "");
+            self.update_len();
         }
     }
 
     pub fn append(&mut self, row: &Row) {
-        self.string = format!("{}{}", self.string, row);
+        self.string.push_str(&row.string);
+        self.state = row.state;
+        self.ending = row.ending;
+        self.update_len();
     }
 
     pub fn split(&mut self, at: usize) -> Row {
-        let (beginning, remainder) = self.string.split_at(at);
-        let row = Row::from(remainder);
-        self.string = beginning.to_string();
+        let byte = self.byte_index(at);
+        let mut row = Row::from(&self.string[byte..]);
+        row.state = self.state;
+        row.ending = self.ending;
+        self.string.truncate(byte);
+        self.update_len();
 
         row
     }
 
+    pub fn ending(&self) -> LineEnding {
+        self.ending
+    }
+
+    pub fn set_ending(&mut self, ending: LineEnding) {
+        self.ending = ending;
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
         self.string.as_bytes()
     }
 
-    pub fn find(&self, query: &str) -> Option<usize> {
-        self.string.find(query)
+    pub fn find(&self, pattern: &Regex, at: usize, direction: SearchDirection) -> Option<usize> {
+        let at = self.byte_index(at);
+
+        let byte = match direction {
+            SearchDirection::Forward => {
+                pattern.find_at(&self.string, at).map(|found| found.start())
+            }
+            SearchDirection::Backward => pattern
+                .find_iter(&self.string)
+                .map(|found| found.start())
+                .take_while(|byte| *byte < at)
+                .last(),
+        }?;
+
+        Some(self.string[..byte].graphemes(true).count())
+    }
+
+    pub fn find_all(&self, pattern: &Regex) -> Vec<Range<usize>> {
+        pattern
+            .find_iter(&self.string)
+            .map(|found| {
+                let start = self.string[..found.start()].graphemes(true).count();
+                start..start + found.as_str().graphemes(true).count()
+            })
+            .collect()
+    }
+
+    pub fn matched(&self, pattern: &Regex, at: usize) -> Option<&str> {
+        self.captures(pattern, self.byte_index(at))
+            .and_then(|captures| captures.get(0))
+            .map(|found| found.as_str())
+    }
+
+    pub fn replacement(
+        &self,
+        pattern: &Regex,
+        replacement: &str,
+        at: usize,
+    ) -> Option<(String, String)> {
+        let captures = self.captures(pattern, self.byte_index(at))?;
+        let mut expanded = String::new();
+        captures.expand(replacement, &mut expanded);
+
+        Some((captures[0].to_string(), expanded))
+    }
+
+    pub fn replacements(
+        &self,
+        pattern: &Regex,
+        replacement: &str,
+        at: usize,
+    ) -> Vec<(usize, String, String)> {
+        let mut replacements = Vec::new();
+        let mut byte = self.byte_index(at);
+        let mut previous_end = None;
+
+        while let Some(captures) = pattern.captures_at(&self.string, byte) {
+            let found = captures.get(0).expect("group 0 is always present");
+
+            if found.is_empty() && previous_end == Some(found.start()) {
+                match self.string[found.start()..].chars().next() {
+                    Some(ch) => byte = found.start() + ch.len_utf8(),
+                    None => break,
+                }
+                continue;
+            }
+
+            let mut expanded = String::new();
+            captures.expand(replacement, &mut expanded);
+            replacements.push((
+                self.string[..found.start()].graphemes(true).count(),
+                found.as_str().to_string(),
+                expanded,
+            ));
+
+            previous_end = Some(found.end());
+            byte = match self.string[found.end()..].chars().next() {
+                _ if !found.is_empty() => found.end(),
+                Some(ch) => found.end() + ch.len_utf8(),
+                None => break,
+            };
+        }
+
+        replacements
+    }
+
+    fn captures(&self, pattern: &Regex, byte: usize) -> Option<Captures<'_>> {
+        pattern
+            .captures_at(&self.string, byte)
+            .filter(|captures| captures.get(0).is_some_and(|found| found.start() == byte))
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn highlight(&mut self, opts: &HighlightingOptions, start: State) -> State {
+        let graphemes: Vec<&str> = self.string.graphemes(true).collect();
+        let mut highlighting = Vec::with_capacity(graphemes.len());
+        let mut state = start;
+        let mut i = 0;
+
+        if let Some(heading) = opts.heading {
+            if state == State::Normal && self.string.starts_with(heading) {
+                highlighting.resize(graphemes.len(), highlighting::Type::Keyword);
+                i = graphemes.len();
+            }
+        }
+
+        while i < graphemes.len() {
+            let rest = &graphemes[i..];
+
+            let open = match state {
+                State::Comment => opts
+                    .multiline_comment
+                    .map(|(_, end)| (end, false, highlighting::Type::Comment)),
+                State::String(index) => opts
+                    .multiline_strings
+                    .get(index)
+                    .map(|(_, end, escapes)| (*end, *escapes, highlighting::Type::String)),
+                State::Normal => None,
+            };
+
+            if let Some((end, escapes, highlight)) = open {
+                let len = if starts_with(rest, end) {
+                    state = State::Normal;
+                    end.len()
+                } else if escapes && rest[0] == "\\" {
+                    rest.len().min(2)
+                } else {
+                    1
+                };
+
+                highlighting.extend(std::iter::repeat_n(highlight, len));
+                i += len;
+                continue;
+            }
+
+            let separated = i == 0 || is_separator(graphemes[i - 1]);
+            let first = rest[0].chars().next().unwrap_or_default();
+
+            if opts
+                .comment
+                .is_some_and(|comment| starts_with(rest, comment))
+            {
+                highlighting.extend(std::iter::repeat_n(highlighting::Type::Comment, rest.len()));
+                break;
+            }
+
+            if let Some((start, _)) = opts
+                .multiline_comment
+                .filter(|(start, _)| starts_with(rest, start))
+            {
+                highlighting.extend(std::iter::repeat_n(
+                    highlighting::Type::Comment,
+                    start.len(),
+                ));
+                i += start.len();
+                state = State::Comment;
+                continue;
+            }
+
+            if let Some((index, (start, _, _))) = opts
+                .multiline_strings
+                .iter()
+                .enumerate()
+                .find(|(_, (start, _, _))| starts_with(rest, start))
+            {
+                highlighting.extend(std::iter::repeat_n(highlighting::Type::String, start.len()));
+                i += start.len();
+                state = State::String(index);
+                continue;
+            }
+
+            if opts.quotes.contains(&first) {
+                let len = quoted_len(rest, rest[0]);
+                highlighting.extend(std::iter::repeat_n(highlighting::Type::String, len));
+                i += len;
+                continue;
+            }
+
+            if opts.characters && first == '\'' {
+                let len = match rest {
+                    [_, "\\", _, "'", ..] => 4,
+                    [_, ch, "'", ..] if *ch != "\\" => 3,
+                    _ => 0,
+                };
+
+                if len > 0 {
+                    highlighting.extend(std::iter::repeat_n(highlighting::Type::Character, len));
+                    i += len;
+                    continue;
+                }
+            }
+
+            if opts.numbers && separated && first.is_ascii_digit() {
+                let mut len = 1;
+
+                while let Some(grapheme) = rest.get(len) {
+                    let is_decimal_point = *grapheme == "."
+                        && rest
+                            .get(len + 1)
+                            .is_some_and(|next| next.starts_with(|ch: char| ch.is_ascii_digit()));
+
+                    if is_decimal_point || !is_separator(grapheme) {
+                        len += 1;
+                    } else {
+                        break;
+                    }
+                }
+
+                highlighting.extend(std::iter::repeat_n(highlighting::Type::Number, len));
+                i += len;
+                continue;
+            }
+
+            if separated && (first.is_alphanumeric() || first == '_' || first == '#') {
+                let mut len = 1;
+
+                while rest
+                    .get(len)
+                    .is_some_and(|grapheme| !is_separator(grapheme))
+                {
+                    len += 1;
+                }
+
+                let word = rest[..len].concat();
+                let highlight = if opts.keywords.contains(&word.as_str()) {
+                    highlighting::Type::Keyword
+                } else if opts.types.contains(&word.as_str())
+                    || (opts.capitalized_types && first.is_uppercase())
+                {
+                    highlighting::Type::Typename
+                } else {
+                    highlighting::Type::None
+                };
+
+                highlighting.extend(std::iter::repeat_n(highlight, len));
+                i += len;
+                continue;
+            }
+
+            highlighting.push(highlighting::Type::None);
+            i += 1;
+        }
+
+        self.highlighting = highlighting;
+        self.state = state;
+
+        state
+    }
+
+    fn byte_index(&self, at: usize) -> usize {
+        self.string
+            .grapheme_indices(true)
+            .nth(at)
+            .map_or(self.string.len(), |(i, _)| i)
+    }
+
+    fn update_len(&mut self) {
+        self.len = self.string.graphemes(true).count();
+    }
+}
+
+fn is_separator(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .all(|ch| ch.is_whitespace() || (ch.is_ascii_punctuation() && ch != '_'))
+}
+
+fn starts_with(graphemes: &[&str], pattern: &str) -> bool {
+    graphemes.len() >= pattern.len() && graphemes[..pattern.len()].concat() == pattern
+}
+
+fn quoted_len(graphemes: &[&str], quote: &str) -> usize {
+    let mut len = 1;
+
+    while let Some(grapheme) = graphemes.get(len) {
+        len += 1;
+
+        if *grapheme == "\\" {
+            len += 1;
+        } else if *grapheme == quote {
+            break;
+        }
+    }
+
+    len.min(graphemes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacements_start_at_the_cursor() {
+        let row = Row::from("aaaa");
+        let pattern = Regex::new("aa").unwrap();
+        let starts = |at| -> Vec<usize> {
+            row.replacements(&pattern, "b", at)
+                .into_iter()
+                .map(|(x, _, _)| x)
+                .collect()
+        };
+
+        assert_eq!(starts(0), [0, 2]);
+        assert_eq!(starts(1), [1]);
+        assert_eq!(starts(3), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn replacement_is_anchored_and_expands_groups() {
+        let row = Row::from("éaaa");
+        let pattern = Regex::new("(a)a").unwrap();
+
+        assert_eq!(row.matched(&pattern, 2), Some("aa"));
+        assert_eq!(row.matched(&pattern, 3), None);
+        assert_eq!(
+            row.replacement(&pattern, "<$1>", 2),
+            Some(("aa".to_string(), "<a>".to_string()))
+        );
+    }
+
+    #[test]
+    fn tabs_expand_to_the_given_width() {
+        let row = Row::from("a\tb\t");
+
+        assert_eq!(row.render(0, 20, 4), "a   b   ");
+        assert_eq!(row.render(0, 20, 8), "a       b       ");
+        assert_eq!((row.column(2, 4), row.column(2, 8)), (4, 8));
+        assert_eq!((row.width(4), row.width(8)), (8, 16));
+        assert_eq!((row.index(3, 4), row.index(3, 8)), (1, 1));
+        assert_eq!((row.index(5, 4), row.index(5, 8)), (3, 1));
+    }
+
+    #[test]
+    fn empty_matches_advance() {
+        let row = Row::from("ab");
+        let pattern = Regex::new("a*").unwrap();
+        let replacements = row.replacements(&pattern, "-", 0);
+
+        assert_eq!(
+            replacements
+                .iter()
+                .map(|(x, old, _)| (*x, old.as_str()))
+                .collect::<Vec<_>>(),
+            [(0, "a"), (2, "")]
+        );
     }
 }
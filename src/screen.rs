@@ -0,0 +1,138 @@
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::{
+    Attribute, Attributes, Color, Print, SetAttribute, SetAttributes, SetBackgroundColor,
+    SetForegroundColor,
+};
+use std::io::{self, Write};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: String,
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: Attributes,
+}
+
+#[derive(Clone)]
+pub struct Screen {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Screen {
+    pub fn new(width: usize, height: usize, fg: Color, bg: Color) -> Screen {
+        let blank = Cell {
+            symbol: " ".to_string(),
+            fg,
+            bg,
+            attrs: Attributes::default(),
+        };
+
+        Screen {
+            width,
+            height,
+            cells: vec![blank; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn print(&mut self, x: usize, y: usize, text: &str, fg: Color, bg: Color) -> usize {
+        if y >= self.height {
+            return 0;
+        }
+
+        let mut column = x;
+
+        for grapheme in text.graphemes(true) {
+            let width = grapheme.width();
+
+            if width == 0 {
+                continue;
+            }
+
+            if column + width > self.width {
+                break;
+            }
+
+            let index = y * self.width + column;
+
+            self.cells[index] = Cell {
+                symbol: grapheme.to_string(),
+                fg,
+                bg,
+                attrs: Attributes::default(),
+            };
+
+            for cell in &mut self.cells[index + 1..index + width] {
+                *cell = Cell {
+                    symbol: String::new(),
+                    fg,
+                    bg,
+                    attrs: Attributes::default(),
+                };
+            }
+
+            column += width;
+        }
+
+        column - x
+    }
+
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, fg: Color, bg: Color) {
+        let width = width.min(self.width.saturating_sub(x));
+
+        self.print(x, y, &" ".repeat(width), fg, bg);
+    }
+
+    pub fn diff(&self, previous: Option<&Screen>, out: &mut impl Write) -> io::Result<()> {
+        let previous = previous
+            .filter(|previous| previous.width == self.width && previous.height == self.height);
+
+        let mut cursor = None;
+        let mut style = None;
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            if cell.symbol.is_empty() || previous.is_some_and(|previous| previous.cells[i] == *cell)
+            {
+                continue;
+            }
+
+            let (x, y) = (i % self.width, i / self.width);
+
+            if cursor != Some((x, y)) {
+                queue!(out, MoveTo(x as u16, y as u16))?;
+            }
+
+            if style != Some((cell.fg, cell.bg, cell.attrs)) {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reset),
+                    SetAttributes(cell.attrs),
+                    SetForegroundColor(cell.fg),
+                    SetBackgroundColor(cell.bg)
+                )?;
+                style = Some((cell.fg, cell.bg, cell.attrs));
+            }
+
+            queue!(out, Print(&cell.symbol))?;
+            cursor = Some((x + cell.symbol.width(), y));
+        }
+
+        if style.is_some() {
+            queue!(out, SetAttribute(Attribute::Reset))?;
+        }
+
+        Ok(())
+    }
+}
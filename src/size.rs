@@ -1,3 +1,4 @@
+#[derive(Clone, Copy)]
 pub struct Size {
     pub width: u16,
     pub height: u16,
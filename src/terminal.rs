@@ -1,19 +1,39 @@
 use crate::Position;
+use crate::Screen;
 use crate::Size;
 use crossterm::cursor::{Hide, MoveTo, Show};
-use crossterm::event::{self, Event};
-use crossterm::execute;
-use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::event::{
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind,
+};
 use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
 use std::io::{self, Write};
+use std::sync::{Mutex, MutexGuard};
+
+static SIZE: Mutex<Option<Size>> = Mutex::new(None);
+static FRAME: Mutex<Option<Screen>> = Mutex::new(None);
 
 pub struct Terminal;
 
 impl Terminal {
     pub fn read_event() -> crossterm::Result<Event> {
         loop {
-            if let Ok(event) = event::read() {
-                return Ok(event);
+            match event::read() {
+                Ok(Event::Key(KeyEvent {
+                    kind: KeyEventKind::Release,
+                    ..
+                })) => {}
+                Ok(Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Moved,
+                    ..
+                })) => {}
+                Ok(Event::Resize(width, height)) => {
+                    Terminal::resize(width, height);
+                    return Ok(Event::Resize(width, height));
+                }
+                Ok(event) => return Ok(event),
+                Err(_) => {}
             }
         }
     }
@@ -26,6 +46,22 @@ impl Terminal {
         execute!(io::stdout(), LeaveAlternateScreen)
     }
 
+    pub fn enable_bracketed_paste() -> crossterm::Result<()> {
+        execute!(io::stdout(), EnableBracketedPaste)
+    }
+
+    pub fn disable_bracketed_paste() -> crossterm::Result<()> {
+        execute!(io::stdout(), DisableBracketedPaste)
+    }
+
+    pub fn enable_mouse_capture() -> crossterm::Result<()> {
+        execute!(io::stdout(), EnableMouseCapture)
+    }
+
+    pub fn disable_mouse_capture() -> crossterm::Result<()> {
+        execute!(io::stdout(), DisableMouseCapture)
+    }
+
     pub fn enable_raw_mode() -> crossterm::Result<()> {
         terminal::enable_raw_mode()
     }
@@ -35,43 +71,70 @@ impl Terminal {
     }
 
     pub fn size() -> crossterm::Result<Size> {
+        let mut size = lock(&SIZE);
+
+        if let Some(size) = *size {
+            return Ok(size);
+        }
+
         let (width, height) = terminal::size()?;
-        Ok(Size::new(width, height.saturating_sub(2)))
+        Ok(*size.insert(Size::new(width, height.saturating_sub(2))))
+    }
+
+    fn resize(width: u16, height: u16) {
+        *lock(&SIZE) = Some(Size::new(width, height.saturating_sub(2)));
     }
 
     pub fn clear_all() -> crossterm::Result<()> {
+        *lock(&FRAME) = None;
         execute!(io::stdout(), Clear(ClearType::All))
     }
 
-    pub fn clear_current_line() -> crossterm::Result<()> {
-        execute!(io::stdout(), Clear(ClearType::CurrentLine))
-    }
+    pub fn draw(screen: Screen, cursor: Option<Position>) -> crossterm::Result<()> {
+        let mut frame = lock(&FRAME);
+        let mut stdout = io::stdout().lock();
 
-    pub fn cursor_hide() -> crossterm::Result<()> {
-        execute!(io::stdout(), Hide)
-    }
+        queue!(stdout, Hide)?;
+        screen.diff(frame.as_ref(), &mut stdout)?;
 
-    pub fn cursor_show() -> crossterm::Result<()> {
-        execute!(io::stdout(), Show)
-    }
+        if let Some(position) = cursor {
+            queue!(stdout, MoveTo(position.x as u16, position.y as u16), Show)?;
+        }
 
-    pub fn cursor_position(position: &Position) -> crossterm::Result<()> {
-        execute!(io::stdout(), MoveTo(position.x, position.y))
-    }
+        *frame = Some(screen);
 
-    pub fn set_bg_color(color: Color) -> crossterm::Result<()> {
-        execute!(io::stdout(), SetBackgroundColor(color))
+        stdout.flush()
     }
 
-    pub fn set_fg_color(color: Color) -> crossterm::Result<()> {
-        execute!(io::stdout(), SetForegroundColor(color))
+    pub fn set_clipboard(text: &str) -> crossterm::Result<()> {
+        let mut stdout = io::stdout();
+        write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+        stdout.flush()
     }
+}
 
-    pub fn reset_color() -> crossterm::Result<()> {
-        execute!(io::stdout(), ResetColor)
-    }
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let buffer = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
 
-    pub fn flush() -> crossterm::Result<()> {
-        io::stdout().flush()
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(buffer >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
     }
+
+    encoded
 }
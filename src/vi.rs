@@ -0,0 +1,404 @@
+use crate::Document;
+use crate::Position;
+use std::fmt;
+
+const MAX_COUNT: usize = 99_999;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Insert,
+    Visual,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::Normal => write!(f, "NORMAL"),
+            Mode::Insert => write!(f, "INSERT"),
+            Mode::Visual => write!(f, "VISUAL"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Down,
+    Up,
+    Right,
+    WordForward,
+    WordBackward,
+    WordEnd,
+    LineStart,
+    LineEnd,
+    FirstLine,
+    LastLine,
+    MatchBracket,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Move(Motion),
+    Operate(Operator, Motion),
+    OperateLines(Operator),
+    Insert(char),
+    Visual,
+    DeleteChar,
+    Paste(bool),
+    Undo,
+    Repeat,
+}
+
+pub enum Parse {
+    Incomplete,
+    Invalid,
+    Complete(Option<usize>, Action),
+}
+
+pub fn parse(keys: &[char]) -> Parse {
+    let (count, rest) = split_count(keys);
+
+    let action = match rest {
+        [] => return Parse::Incomplete,
+        [op @ ('d' | 'c' | 'y'), tail @ ..] => {
+            let operator = match op {
+                'd' => Operator::Delete,
+                'c' => Operator::Change,
+                _ => Operator::Yank,
+            };
+            let (motion_count, tail) = split_count(tail);
+            let count = match (count, motion_count) {
+                (None, None) => None,
+                (count, motion_count) => {
+                    let count = count.unwrap_or(1).saturating_mul(motion_count.unwrap_or(1));
+                    Some(count.min(MAX_COUNT))
+                }
+            };
+
+            return match tail {
+                [] => Parse::Incomplete,
+                [ch] if ch == op => Parse::Complete(count, Action::OperateLines(operator)),
+                _ => match parse_motion(tail) {
+                    Parse::Complete(_, Action::Move(motion)) => {
+                        Parse::Complete(count, Action::Operate(operator, motion))
+                    }
+                    parse => parse,
+                },
+            };
+        }
+        ['i' | 'a' | 'I' | 'A' | 'o' | 'O'] => Action::Insert(rest[0]),
+        ['v'] => Action::Visual,
+        ['x'] => Action::DeleteChar,
+        ['p'] => Action::Paste(false),
+        ['P'] => Action::Paste(true),
+        ['u'] => Action::Undo,
+        ['.'] => Action::Repeat,
+        _ => return parse_motion(rest).with_count(count),
+    };
+
+    Parse::Complete(count, action)
+}
+
+impl Parse {
+    fn with_count(self, count: Option<usize>) -> Parse {
+        match self {
+            Parse::Complete(_, action) => Parse::Complete(count, action),
+            parse => parse,
+        }
+    }
+}
+
+fn parse_motion(keys: &[char]) -> Parse {
+    let motion = match keys {
+        ['h'] => Motion::Left,
+        ['j'] => Motion::Down,
+        ['k'] => Motion::Up,
+        ['l'] => Motion::Right,
+        ['w'] => Motion::WordForward,
+        ['b'] => Motion::WordBackward,
+        ['e'] => Motion::WordEnd,
+        ['0'] => Motion::LineStart,
+        ['$'] => Motion::LineEnd,
+        ['g'] => return Parse::Incomplete,
+        ['g', 'g'] => Motion::FirstLine,
+        ['G'] => Motion::LastLine,
+        ['%'] => Motion::MatchBracket,
+        _ => return Parse::Invalid,
+    };
+
+    Parse::Complete(None, Action::Move(motion))
+}
+
+fn split_count(keys: &[char]) -> (Option<usize>, &[char]) {
+    let digits = keys
+        .iter()
+        .enumerate()
+        .take_while(|(i, ch)| ch.is_ascii_digit() && (*i > 0 || **ch != '0'))
+        .count();
+
+    if digits == 0 {
+        return (None, keys);
+    }
+
+    let count = keys[..digits]
+        .iter()
+        .collect::<String>()
+        .parse::<usize>()
+        .map_or(MAX_COUNT, |count| count.min(MAX_COUNT));
+
+    (Some(count), &keys[digits..])
+}
+
+impl Motion {
+    pub fn is_linewise(self) -> bool {
+        matches!(
+            self,
+            Motion::Up | Motion::Down | Motion::FirstLine | Motion::LastLine
+        )
+    }
+
+    pub fn is_inclusive(self) -> bool {
+        matches!(
+            self,
+            Motion::WordEnd | Motion::LineEnd | Motion::MatchBracket
+        )
+    }
+
+    pub fn target(self, document: &Document, from: Position, count: Option<usize>) -> Position {
+        let repeat = count.unwrap_or(1);
+        let last = document.len().saturating_sub(1);
+        let row_len = |y: usize| document.row(y).map_or(0, |row| row.len());
+
+        match self {
+            Motion::Left => Position::new(from.x.saturating_sub(repeat), from.y),
+            Motion::Right => {
+                Position::new(from.x.saturating_add(repeat).min(row_len(from.y)), from.y)
+            }
+            Motion::Up | Motion::Down => {
+                let y = if self == Motion::Up {
+                    from.y.saturating_sub(repeat)
+                } else {
+                    from.y.saturating_add(repeat).min(last)
+                };
+                let tab_size = document.tab_size();
+                let column = document
+                    .row(from.y)
+                    .map_or(0, |row| row.column(from.x, tab_size));
+                let x = document.row(y).map_or(0, |row| row.index(column, tab_size));
+
+                Position::new(x, y)
+            }
+            Motion::WordForward => (0..repeat).fold(from, |at, _| word_forward(document, at)),
+            Motion::WordBackward => (0..repeat).fold(from, |at, _| word_backward(document, at)),
+            Motion::WordEnd => (0..repeat).fold(from, |at, _| word_end(document, at)),
+            Motion::LineStart => Position::new(0, from.y),
+            Motion::LineEnd => {
+                let y = from.y.saturating_add(repeat - 1).min(last);
+                Position::new(row_len(y).saturating_sub(1), y)
+            }
+            Motion::MatchBracket => document.matching_bracket(&from).unwrap_or(from),
+            Motion::FirstLine | Motion::LastLine => {
+                let y = match (self, count) {
+                    (_, Some(line)) => line.saturating_sub(1).min(last),
+                    (Motion::FirstLine, None) => 0,
+                    _ => last,
+                };
+
+                Position::new(first_non_blank(document, y), y)
+            }
+        }
+    }
+}
+
+pub fn first_non_blank(document: &Document, y: usize) -> usize {
+    document.row(y).map_or(0, |row| {
+        row.graphemes()
+            .iter()
+            .take_while(|grapheme| grapheme.chars().all(char::is_whitespace))
+            .count()
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Empty,
+    Word,
+    Punctuation,
+}
+
+struct Walker<'a> {
+    document: &'a Document,
+    position: Position,
+    graphemes: Vec<&'a str>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(document: &'a Document, position: Position) -> Walker<'a> {
+        let graphemes = document
+            .row(position.y)
+            .map_or_else(Vec::new, |row| row.graphemes());
+
+        Walker {
+            document,
+            position,
+            graphemes,
+        }
+    }
+
+    fn class(&self) -> Class {
+        if self.graphemes.is_empty() {
+            return Class::Empty;
+        }
+
+        match self.graphemes.get(self.position.x) {
+            Some(grapheme) if grapheme.chars().all(char::is_whitespace) => Class::Blank,
+            Some(grapheme) if grapheme.chars().all(|ch| ch.is_alphanumeric() || ch == '_') => {
+                Class::Word
+            }
+            Some(_) => Class::Punctuation,
+            None => Class::Blank,
+        }
+    }
+
+    fn forward(&mut self) -> bool {
+        if self.position.x < self.graphemes.len() {
+            self.position.x += 1;
+            true
+        } else if self.position.y + 1 < self.document.len() {
+            *self = Walker::new(self.document, Position::new(0, self.position.y + 1));
+            true
+        } else {
+            false
+        }
+    }
+
+    fn backward(&mut self) -> bool {
+        if self.position.x > 0 {
+            self.position.x -= 1;
+            true
+        } else if self.position.y > 0 {
+            *self = Walker::new(self.document, Position::new(0, self.position.y - 1));
+            self.position.x = self.graphemes.len();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn word_forward(document: &Document, from: Position) -> Position {
+    let mut walker = Walker::new(document, from);
+    let start = walker.class();
+
+    if start != Class::Blank {
+        while walker.class() == start {
+            if !walker.forward() {
+                return walker.position;
+            }
+        }
+    }
+
+    while walker.class() == Class::Blank && walker.forward() {}
+
+    walker.position
+}
+
+fn word_backward(document: &Document, from: Position) -> Position {
+    let mut walker = Walker::new(document, from);
+
+    if !walker.backward() {
+        return walker.position;
+    }
+
+    while walker.class() == Class::Blank && walker.backward() {}
+
+    let class = walker.class();
+
+    while walker.backward() {
+        if walker.class() != class {
+            walker.forward();
+            break;
+        }
+    }
+
+    walker.position
+}
+
+fn word_end(document: &Document, from: Position) -> Position {
+    let mut walker = Walker::new(document, from);
+
+    if !walker.forward() {
+        return walker.position;
+    }
+
+    while matches!(walker.class(), Class::Blank | Class::Empty) && walker.forward() {}
+
+    let class = walker.class();
+
+    while walker.forward() {
+        if walker.class() != class {
+            walker.backward();
+            break;
+        }
+    }
+
+    walker.position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn counts_multiply() {
+        assert!(matches!(
+            parse(&keys("2d3w")),
+            Parse::Complete(
+                Some(6),
+                Action::Operate(Operator::Delete, Motion::WordForward)
+            )
+        ));
+        assert!(matches!(
+            parse(&keys("dw")),
+            Parse::Complete(None, Action::Operate(..))
+        ));
+    }
+
+    #[test]
+    fn huge_counts_are_capped() {
+        for input in [
+            "9999999999d9999999999w",
+            "99999999999999999999999dd",
+            "99999999999G",
+        ] {
+            match parse(&keys(input)) {
+                Parse::Complete(Some(count), _) => assert_eq!(count, MAX_COUNT, "{input}"),
+                _ => panic!("{input} did not parse"),
+            }
+        }
+    }
+
+    #[test]
+    fn motions_saturate_at_the_document_edges() {
+        let document = Document::default();
+        let from = Position::new(usize::MAX - 1, usize::MAX - 1);
+
+        for motion in [Motion::Right, Motion::Down, Motion::LineEnd] {
+            let target = motion.target(&document, from, Some(MAX_COUNT));
+            assert_eq!(target.y, if motion == Motion::Right { from.y } else { 0 });
+        }
+    }
+}
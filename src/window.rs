@@ -0,0 +1,216 @@
+use crate::Position;
+use std::mem;
+
+const MIN_RATIO: u16 = 10;
+const MAX_RATIO: u16 = 90;
+
+#[derive(Default, Clone, Copy)]
+pub struct Pane {
+    pub buffer: usize,
+    pub cursor_position: Position,
+    pub offset: Position,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Arrangement {
+    pub panes: Vec<(usize, Rect)>,
+    pub separators: Vec<Rect>,
+}
+
+impl Arrangement {
+    pub fn rect(&self, pane: usize) -> Option<Rect> {
+        self.panes
+            .iter()
+            .find(|(index, _)| *index == pane)
+            .map(|(_, rect)| *rect)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    Horizontal,
+    Vertical,
+}
+
+pub enum Layout {
+    Pane(usize),
+    Split {
+        split: Split,
+        ratio: u16,
+        first: Box<Layout>,
+        second: Box<Layout>,
+    },
+}
+
+impl Layout {
+    pub fn split(&mut self, pane: usize, new_pane: usize, split: Split) -> bool {
+        match self {
+            Layout::Pane(index) if *index == pane => {
+                *self = Layout::Split {
+                    split,
+                    ratio: 50,
+                    first: Box::new(Layout::Pane(pane)),
+                    second: Box::new(Layout::Pane(new_pane)),
+                };
+                true
+            }
+            Layout::Pane(_) => false,
+            Layout::Split { first, second, .. } => {
+                first.split(pane, new_pane, split) || second.split(pane, new_pane, split)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, pane: usize) -> bool {
+        let removed = self.remove_leaf(pane);
+
+        if removed {
+            self.renumber(pane);
+        }
+
+        removed
+    }
+
+    fn remove_leaf(&mut self, pane: usize) -> bool {
+        let Layout::Split { first, second, .. } = self else {
+            return false;
+        };
+
+        let sibling = match (first.as_ref(), second.as_ref()) {
+            (Layout::Pane(index), _) if *index == pane => {
+                mem::replace(second, Box::new(Layout::Pane(0)))
+            }
+            (_, Layout::Pane(index)) if *index == pane => {
+                mem::replace(first, Box::new(Layout::Pane(0)))
+            }
+            _ => return first.remove_leaf(pane) || second.remove_leaf(pane),
+        };
+
+        *self = *sibling;
+        true
+    }
+
+    fn renumber(&mut self, removed: usize) {
+        match self {
+            Layout::Pane(index) if *index > removed => *index -= 1,
+            Layout::Pane(_) => {}
+            Layout::Split { first, second, .. } => {
+                first.renumber(removed);
+                second.renumber(removed);
+            }
+        }
+    }
+
+    pub fn resize(&mut self, pane: usize, split: Split, delta: i16) -> bool {
+        let Layout::Split {
+            split: kind,
+            ratio,
+            first,
+            second,
+        } = self
+        else {
+            return false;
+        };
+
+        if first.resize(pane, split, delta) || second.resize(pane, split, delta) {
+            return true;
+        }
+
+        if *kind != split {
+            return false;
+        }
+
+        let delta = if first.contains(pane) {
+            delta
+        } else if second.contains(pane) {
+            -delta
+        } else {
+            return false;
+        };
+
+        *ratio = ratio
+            .saturating_add_signed(delta)
+            .clamp(MIN_RATIO, MAX_RATIO);
+        true
+    }
+
+    pub fn contains(&self, pane: usize) -> bool {
+        match self {
+            Layout::Pane(index) => *index == pane,
+            Layout::Split { first, second, .. } => first.contains(pane) || second.contains(pane),
+        }
+    }
+
+    pub fn first_pane(&self) -> usize {
+        match self {
+            Layout::Pane(index) => *index,
+            Layout::Split { first, .. } => first.first_pane(),
+        }
+    }
+
+    pub fn arrange(&self, area: Rect, arrangement: &mut Arrangement) {
+        match self {
+            Layout::Pane(index) => arrangement.panes.push((*index, area)),
+            Layout::Split {
+                split: Split::Horizontal,
+                ratio,
+                first,
+                second,
+            } => {
+                let top = (area.height * *ratio as usize / 100)
+                    .clamp(1, area.height.saturating_sub(1).max(1));
+                first.arrange(Rect::new(area.x, area.y, area.width, top), arrangement);
+                second.arrange(
+                    Rect::new(
+                        area.x,
+                        area.y + top,
+                        area.width,
+                        area.height.saturating_sub(top),
+                    ),
+                    arrangement,
+                );
+            }
+            Layout::Split {
+                split: Split::Vertical,
+                ratio,
+                first,
+                second,
+            } => {
+                let left = (area.width * *ratio as usize / 100)
+                    .clamp(1, area.width.saturating_sub(2).max(1));
+                first.arrange(Rect::new(area.x, area.y, left, area.height), arrangement);
+                arrangement
+                    .separators
+                    .push(Rect::new(area.x + left, area.y, 1, area.height));
+                second.arrange(
+                    Rect::new(
+                        area.x + left + 1,
+                        area.y,
+                        area.width.saturating_sub(left + 1),
+                        area.height,
+                    ),
+                    arrangement,
+                );
+            }
+        }
+    }
+}
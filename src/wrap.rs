@@ -0,0 +1,143 @@
+use crate::Document;
+use crate::Position;
+use std::ops::Range;
+use unicode_width::UnicodeWidthStr;
+
+pub const INDICATOR: &str = "↪";
+
+pub fn segments(document: &Document, y: usize, width: usize) -> Vec<Range<usize>> {
+    match document.row(y) {
+        Some(row) => row.wrap(
+            width,
+            width.saturating_sub(INDICATOR.width()),
+            document.tab_size(),
+        ),
+        None => vec![Range { start: 0, end: 0 }],
+    }
+}
+
+pub fn segment(document: &Document, y: usize, segment: usize, width: usize) -> Range<usize> {
+    let segments = segments(document, y, width);
+    let last = segments.len() - 1;
+
+    segments[segment.min(last)].clone()
+}
+
+pub fn locate(document: &Document, position: &Position, width: usize) -> (usize, usize) {
+    let segments = segments(document, position.y, width);
+    let segment = segments
+        .iter()
+        .position(|range| position.x < range.end)
+        .unwrap_or(segments.len() - 1);
+
+    let tab_size = document.tab_size();
+    let column = document.row(position.y).map_or(0, |row| {
+        row.column(position.x, tab_size) - row.column(segments[segment].start, tab_size)
+    });
+
+    (segment, column + indent(segment))
+}
+
+pub fn index(document: &Document, y: usize, segment: usize, column: usize, width: usize) -> usize {
+    let segments = segments(document, y, width);
+    let last = segments.len() - 1;
+    let range = &segments[segment.min(last)];
+
+    let row = match document.row(y) {
+        Some(row) => row,
+        None => return 0,
+    };
+
+    let tab_size = document.tab_size();
+    let x = row.index(
+        row.column(range.start, tab_size) + column.saturating_sub(indent(segment)),
+        tab_size,
+    );
+    let max = if segment < last {
+        range.end.saturating_sub(1).max(range.start)
+    } else {
+        range.end
+    };
+
+    x.clamp(range.start, max)
+}
+
+pub fn previous(document: &Document, line: (usize, usize), width: usize) -> Option<(usize, usize)> {
+    match line {
+        (y, segment) if segment > 0 => Some((y, segment - 1)),
+        (y, _) if y > 0 => Some((y - 1, segments(document, y - 1, width).len() - 1)),
+        _ => None,
+    }
+}
+
+pub fn next(document: &Document, line: (usize, usize), width: usize) -> Option<(usize, usize)> {
+    let (y, segment) = line;
+
+    if segment + 1 < segments(document, y, width).len() {
+        Some((y, segment + 1))
+    } else if y < document.len() {
+        Some((y + 1, 0))
+    } else {
+        None
+    }
+}
+
+pub fn distance(
+    document: &Document,
+    from: (usize, usize),
+    to: (usize, usize),
+    width: usize,
+    limit: usize,
+) -> Option<usize> {
+    let mut line = from;
+
+    for distance in 0..limit {
+        if line == to {
+            return Some(distance);
+        }
+
+        line = next(document, line, width)?;
+    }
+
+    None
+}
+
+pub fn follow(
+    document: &Document,
+    offset: &mut Position,
+    cursor: &Position,
+    width: usize,
+    height: usize,
+) {
+    offset.x = offset.x.min(segments(document, offset.y, width).len() - 1);
+
+    let target = (cursor.y, locate(document, cursor, width).0);
+
+    if target < (offset.y, offset.x) {
+        *offset = Position::new(target.1, target.0);
+        return;
+    }
+
+    if distance(document, (offset.y, offset.x), target, width, height).is_some() {
+        return;
+    }
+
+    let mut top = target;
+
+    for _ in 1..height {
+        match previous(document, top, width) {
+            Some(line) => top = line,
+            None => break,
+        }
+    }
+
+    *offset = Position::new(top.1, top.0);
+}
+
+fn indent(segment: usize) -> usize {
+    if segment > 0 {
+        INDICATOR.width()
+    } else {
+        0
+    }
+}